@@ -0,0 +1,21 @@
+/// Surface properties of a part of a mesh, referenced with `usemtl` in .obj files.
+/// Colors from .mtl files are stored as their average brightness, since the terminal only shows brightness.
+/// Properties that the .mtl file does not set are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// How bright highlights are, from 0 to 1 (`Ks`)
+    pub specular: Option<f32>,
+    /// Larger values give smaller and sharper highlights, usually up to 1000 (`Ns`)
+    pub shininess: Option<f32>,
+}
+
+impl Material {
+    pub fn new(name: &str) -> Self {
+        Material {
+            name: name.to_owned(),
+            specular: None,
+            shininess: None,
+        }
+    }
+}
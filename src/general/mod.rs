@@ -1,2 +1,3 @@
+pub mod material;
 pub mod positions_2d;
 pub mod positions_3d;
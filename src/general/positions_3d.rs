@@ -1,3 +1,4 @@
+use crate::general::material::Material;
 use crate::general::positions_2d::{Point as Point2, Triangle as Triangle2};
 #[derive(Debug, PartialEq, Clone)]
 pub struct Point {
@@ -6,10 +7,57 @@ pub struct Point {
     pub z: f32,
 }
 
+/// A position with double precision, for coordinates that are too far from the origin for `f32`,
+/// like the hundreds of thousands of metres in georeferenced survey data.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct GlobalPoint {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// Marks a triangle without a value in one of the optional arrays of `FaceData`
+pub const NO_INDEX: u32 = u32::MAX;
+
+/// Triangle mesh stored as structure of arrays, so that large meshes stay compact in memory.
+/// Indices are stored as `u32`, which limits a mesh to about four billion vertices and triangles.
 #[derive(Clone, Debug)]
 pub struct Mesh {
     pub points: Vec<Point>,
-    pub indices_triangles: Vec<IndicesTriangle>,
+    /// Indices into `points`, counterclockwise when seen from outside
+    pub triangles: Vec<[u32; 3]>,
+    /// One normal per triangle, in the same order as `triangles`
+    pub face_normals: Vec<Point>,
+    /// Vertex normals, referenced by `FaceData::normal_indices`
+    pub normals: Vec<Point>,
+    /// Texture coordinates, referenced by `FaceData::uv_indices`
+    pub uvs: Vec<Point2>,
+    /// Names of groups and objects, referenced by `FaceData::groups`
+    pub groups: Vec<String>,
+    /// Referenced by `FaceData::materials`
+    pub materials: Vec<Material>,
+    pub face_data: FaceData,
+    /// Global position that `points` are relative to.
+    /// Zero, unless the mesh was far away from the origin in the file.
+    pub origin: GlobalPoint,
+}
+
+/// Optional data for each triangle of a `Mesh`.
+/// An array is `None` if no triangle has that kind of data, otherwise it has one entry per triangle,
+/// and `NO_INDEX` marks the triangles without a value.
+#[derive(Clone, Debug, Default)]
+pub struct FaceData {
+    /// Indices into `Mesh::normals`, in the same order as the vertex indices
+    pub normal_indices: Option<Vec<[u32; 3]>>,
+    /// Indices into `Mesh::uvs`, in the same order as the vertex indices
+    pub uv_indices: Option<Vec<[u32; 3]>>,
+    /// Indices into `Mesh::groups`
+    pub groups: Option<Vec<u32>>,
+    /// Indices into `Mesh::materials`
+    pub materials: Option<Vec<u32>>,
+    /// Identifies the polygon that the triangle was split from,
+    /// all triangles of a quad or n-gon in the file share the same value
+    pub polygons: Option<Vec<u32>>,
 }
 
 #[derive(Clone, Debug)]
@@ -20,17 +68,9 @@ pub struct Triangle<'a> {
     pub normal: &'a Point, // maybe this should have different lifetime
 }
 
-#[derive(Clone, Debug)]
-pub struct IndicesTriangle {
-    pub p1: usize,
-    pub p2: usize,
-    pub p3: usize,
-    pub normal: Point,
-}
-
 pub struct BoundingBox(Point, Point);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Degrees(pub f32);
 
 // TODO add two methods for multiplication and addition on each component of the point
@@ -115,16 +155,89 @@ impl Point {
     }
 }
 
+impl GlobalPoint {
+    pub fn from_array(array: [f64; 3]) -> Self {
+        GlobalPoint {
+            x: array[0],
+            y: array[1],
+            z: array[2],
+        }
+    }
+
+    pub fn to_array(&self) -> [f64; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    /// The global position of a point that is given relative to `self`
+    pub fn add_local(&self, point: &Point) -> GlobalPoint {
+        GlobalPoint {
+            x: self.x + f64::from(point.x),
+            y: self.y + f64::from(point.y),
+            z: self.z + f64::from(point.z),
+        }
+    }
+
+    /// Like `add_local`, but without the digits that the `f32` offset does not have, for positions that are shown.
+    /// `f64::from(0.1_f32)` is 0.10000000149011612, this keeps it at 0.1
+    pub fn add_local_rounded(&self, point: &Point) -> GlobalPoint {
+        let add_rounded = |origin: f64, offset: f32| {
+            // the decimals of the shortest text that gives back the same f32
+            let offset_text = offset.to_string();
+            let decimals = offset_text
+                .split_once('.')
+                .map_or(0, |(_, decimals)| decimals.len());
+            let scale = 10_f64.powi(decimals as i32);
+            ((origin + f64::from(offset)) * scale).round() / scale
+        };
+        GlobalPoint {
+            x: add_rounded(self.x, point.x),
+            y: add_rounded(self.y, point.y),
+            z: add_rounded(self.z, point.z),
+        }
+    }
+
+    /// The position of `point` relative to `self`, rounded to single precision
+    pub fn local_offset_to(&self, point: &GlobalPoint) -> Point {
+        Point {
+            x: (point.x - self.x) as f32,
+            y: (point.y - self.y) as f32,
+            z: (point.z - self.z) as f32,
+        }
+    }
+
+    /// Like `dot_product`, but with double precision
+    pub fn dot_product(&self, direction: &Point) -> f64 {
+        self.x * direction.x as f64 + self.y * direction.y as f64 + self.z * direction.z as f64
+    }
+
+    /// Applies a linear function of single precision points with double precision,
+    /// by combining the results of `f` for the three unit vectors.
+    pub fn map_linear<F>(&self, f: F) -> Self
+    where
+        F: Fn(&Point) -> Point,
+    {
+        let columns = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+            .map(|unit_vector| f(&Point::from_array(unit_vector)).to_array());
+        let component = |row: usize| {
+            (0..3)
+                .map(|column| columns[column][row] as f64 * self.to_array()[column])
+                .sum()
+        };
+        GlobalPoint::from_array([component(0), component(1), component(2)])
+    }
+}
+
 impl<'a> Triangle<'a> {
     pub fn from_indices(
-        indices_triangle: &'a IndicesTriangle,
-        points: &'a Vec<Point>,
+        indices: &[u32; 3],
+        normal: &'a Point,
+        points: &'a [Point],
     ) -> Option<Self> {
         Some(Triangle {
-            p1: points.get(indices_triangle.p1)?,
-            p2: points.get(indices_triangle.p2)?,
-            p3: points.get(indices_triangle.p3)?,
-            normal: &indices_triangle.normal,
+            p1: points.get(indices[0] as usize)?,
+            p2: points.get(indices[1] as usize)?,
+            p3: points.get(indices[2] as usize)?,
+            normal,
         })
     }
 
@@ -235,43 +348,215 @@ impl<'a> Triangle<'a> {
     }
 }
 
-impl IndicesTriangle {
-    pub fn triangle_points<'a>(&self, points: &'a Vec<Point>) -> [&'a Point; 3] {
-        [&points[self.p1], &points[self.p2], &points[self.p3]]
+impl Mesh {
+    pub fn new() -> Self {
+        Mesh {
+            points: Vec::new(),
+            triangles: Vec::new(),
+            face_normals: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            groups: Vec::new(),
+            materials: Vec::new(),
+            face_data: FaceData::default(),
+            origin: GlobalPoint::default(),
+        }
+    }
+
+    /// The position of a point of the mesh in the original coordinates
+    pub fn to_global(&self, point: &Point) -> GlobalPoint {
+        self.origin.add_local(point)
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Adds a triangle without any optional data and returns its index
+    pub fn push_triangle(&mut self, indices: [usize; 3], normal: Point) -> usize {
+        let index = self.triangles.len();
+        self.triangles.push(indices.map(to_mesh_index));
+        self.face_normals.push(normal);
+        self.face_data.push_empty();
+        index
+    }
+
+    pub fn triangle_indices(&self, triangle: usize) -> [usize; 3] {
+        self.triangles[triangle].map(|index| index as usize)
+    }
+
+    pub fn triangle_points(&self, triangle: usize) -> [&Point; 3] {
+        self.triangle_indices(triangle)
+            .map(|index| &self.points[index])
+    }
+
+    /// The triangle has repeated vertices, or an area that is too small to measure compared to its edges.
+    /// Independent of the units of the mesh, so tiny triangles are not degenerate if they have a shape
+    pub fn is_degenerate_triangle(&self, triangle: usize) -> bool {
+        let [i1, i2, i3] = self.triangle_indices(triangle);
+        if i1 == i2 || i2 == i3 || i3 == i1 {
+            return true;
+        }
+        let [p1, p2, p3] = self.triangle_points(triangle);
+        let edges = [p2.relative_to(p1), p3.relative_to(p2), p1.relative_to(p3)];
+        let longest_edge_squared = edges
+            .iter()
+            .map(|edge| dot_product(edge, edge))
+            .fold(0.0, f32::max);
+        // twice the area, compared to the area of a square over the longest edge
+        let cross = cross_product(edges[0].clone(), p3.relative_to(p1));
+        dot_product(&cross, &cross).sqrt() <= f32::EPSILON * longest_edge_squared
+    }
+
+    pub fn normal_indices(&self, triangle: usize) -> Option<[usize; 3]> {
+        get_indices(&self.face_data.normal_indices, triangle)
+    }
+
+    pub fn uv_indices(&self, triangle: usize) -> Option<[usize; 3]> {
+        get_indices(&self.face_data.uv_indices, triangle)
+    }
+
+    pub fn group(&self, triangle: usize) -> Option<usize> {
+        get_index(&self.face_data.groups, triangle)
     }
 
-    pub fn make_clockwise(&mut self, points: &Vec<Point>) {
-        // TODO use dot product of self.normal to determine the correct order of verts
-        // then change order of verts
-        // optionally also set normal to more precise (probably don't)
-        if Triangle::get_normal(&self.triangle_points(points)) == self.normal {
+    pub fn material(&self, triangle: usize) -> Option<usize> {
+        get_index(&self.face_data.materials, triangle)
+    }
+
+    pub fn polygon(&self, triangle: usize) -> Option<usize> {
+        get_index(&self.face_data.polygons, triangle)
+    }
+
+    pub fn set_normal_indices(&mut self, triangle: usize, normal_indices: [usize; 3]) {
+        let count = self.triangles.len();
+        get_or_insert_array(&mut self.face_data.normal_indices, count, [NO_INDEX; 3])[triangle] =
+            normal_indices.map(to_mesh_index);
+    }
+
+    pub fn set_uv_indices(&mut self, triangle: usize, uv_indices: [usize; 3]) {
+        let count = self.triangles.len();
+        get_or_insert_array(&mut self.face_data.uv_indices, count, [NO_INDEX; 3])[triangle] =
+            uv_indices.map(to_mesh_index);
+    }
+
+    pub fn set_group(&mut self, triangle: usize, group: usize) {
+        let count = self.triangles.len();
+        get_or_insert_array(&mut self.face_data.groups, count, NO_INDEX)[triangle] =
+            to_mesh_index(group);
+    }
+
+    pub fn set_material(&mut self, triangle: usize, material: usize) {
+        let count = self.triangles.len();
+        get_or_insert_array(&mut self.face_data.materials, count, NO_INDEX)[triangle] =
+            to_mesh_index(material);
+    }
+
+    pub fn set_polygon(&mut self, triangle: usize, polygon: usize) {
+        let count = self.triangles.len();
+        get_or_insert_array(&mut self.face_data.polygons, count, NO_INDEX)[triangle] =
+            to_mesh_index(polygon);
+    }
+
+    /// Reverses the winding order if it does not match the direction of the face normal.
+    pub fn make_clockwise(&mut self, triangle: usize) {
+        // Comparing the direction instead of the exact normal,
+        // since the computed normal is rarely exactly equal to the stored one
+        let alignment = dot_product(
+            &Triangle::get_normal(&self.triangle_points(triangle)),
+            &self.face_normals[triangle],
+        );
+        // degenerate triangles have a NaN normal and no winding order to fix
+        if alignment.is_nan() || alignment >= 0.0 {
             return;
         }
+        self.flip_winding(triangle);
+    }
 
-        let p3_clone = self.p3;
-        self.p3 = self.p2;
-        self.p2 = p3_clone;
+    /// Reverses the winding order, which makes the triangle face the other way.
+    /// Does not change the face normal.
+    pub fn flip_winding(&mut self, triangle: usize) {
+        self.triangles[triangle].swap(1, 2);
+        if let Some(normal_indices) = &mut self.face_data.normal_indices {
+            normal_indices[triangle].swap(1, 2);
+        }
+        if let Some(uv_indices) = &mut self.face_data.uv_indices {
+            uv_indices[triangle].swap(1, 2);
+        }
+    }
+
+    /// Removes the triangles where `keep` is false, together with their face data
+    pub fn retain_triangles(&mut self, keep: &[bool]) {
+        retain(&mut self.triangles, keep);
+        retain(&mut self.face_normals, keep);
+        self.face_data.retain(keep);
     }
 }
 
-impl Mesh {
-    pub fn new() -> Self {
-        Mesh {
-            points: Vec::new(),
-            indices_triangles: Vec::new(),
-        }
+impl FaceData {
+    fn push_empty(&mut self) {
+        push_if_present(&mut self.normal_indices, [NO_INDEX; 3]);
+        push_if_present(&mut self.uv_indices, [NO_INDEX; 3]);
+        push_if_present(&mut self.groups, NO_INDEX);
+        push_if_present(&mut self.materials, NO_INDEX);
+        push_if_present(&mut self.polygons, NO_INDEX);
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_if_present(&mut self.normal_indices, keep);
+        retain_if_present(&mut self.uv_indices, keep);
+        retain_if_present(&mut self.groups, keep);
+        retain_if_present(&mut self.materials, keep);
+        retain_if_present(&mut self.polygons, keep);
+    }
+}
+
+/// Converts an index to the type stored in a `Mesh`
+pub fn to_mesh_index(index: usize) -> u32 {
+    match u32::try_from(index) {
+        Ok(index) if index != NO_INDEX => index,
+        _ => panic!("index {index} is too large for a mesh"),
+    }
+}
+
+fn get_index(array: &Option<Vec<u32>>, triangle: usize) -> Option<usize> {
+    let index = array.as_ref()?[triangle];
+    (index != NO_INDEX).then_some(index as usize)
+}
+
+fn get_indices(array: &Option<Vec<[u32; 3]>>, triangle: usize) -> Option<[usize; 3]> {
+    let indices = array.as_ref()?[triangle];
+    (indices[0] != NO_INDEX).then(|| indices.map(|index| index as usize))
+}
+
+fn get_or_insert_array<T: Copy>(array: &mut Option<Vec<T>>, count: usize, empty: T) -> &mut Vec<T> {
+    array.get_or_insert_with(|| vec![empty; count])
+}
+
+fn push_if_present<T>(array: &mut Option<Vec<T>>, value: T) {
+    if let Some(array) = array {
+        array.push(value);
+    }
+}
+
+fn retain<T>(array: &mut Vec<T>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    array.retain(|_| *keep.next().unwrap());
+}
+
+fn retain_if_present<T>(array: &mut Option<Vec<T>>, keep: &[bool]) {
+    if let Some(array) = array {
+        retain(array, keep);
     }
 }
 
 impl BoundingBox {
+    /// Returns an inverted box that becomes valid as soon as a point is added
     fn initialize() -> Self {
-        let zero_point = Point {
-            x: 0.,
-            y: 0.,
-            z: 0.,
-        };
-        // Do we really need to clone here?
-        BoundingBox(zero_point.clone(), zero_point)
+        BoundingBox(
+            Point::from_array([f32::INFINITY; 3]),
+            Point::from_array([f32::NEG_INFINITY; 3]),
+        )
     }
 
     // TODO DRY
@@ -297,6 +582,7 @@ impl BoundingBox {
     }
 
     // TODO maybe move public functions to top of impl block?
+    /// The box is not valid if `points` is empty
     pub fn new(points: &Vec<Point>) -> Self {
         let mut bounding_box = BoundingBox::initialize();
         for point in points {
@@ -305,6 +591,10 @@ impl BoundingBox {
         bounding_box
     }
 
+    pub fn get_min_max(&self) -> [Point; 2] {
+        [self.0.clone(), self.1.clone()]
+    }
+
     pub fn get_center(&self) -> Point {
         let get_middle = |point1, point2| (point1 + point2) / 2.0;
         self.0.combine(&self.1, get_middle)
@@ -364,7 +654,9 @@ pub fn distance_from_origo(point: &Point) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::BoundingBox;
-    use crate::general::positions_3d::{cross_product, distance, Point as Point3};
+    use crate::general::positions_3d::{
+        cross_product, distance, GlobalPoint, Mesh, Point as Point3,
+    };
 
     #[test]
     fn test_cross_product() {
@@ -391,6 +683,31 @@ mod tests {
         assert_eq!(cross_product_1, cross_product_2);
     }
 
+    #[test]
+    fn test_shown_global_positions_are_rounded() {
+        let origin = GlobalPoint::from_array([2_500_000.0, 0.0, -7.0]);
+        let point = Point3::from_array([0.1, 3.132512, 1.5]);
+        assert_eq!(origin.add_local(&point).y, f64::from(3.132512_f32));
+        assert_eq!(
+            origin.add_local_rounded(&point).to_array(),
+            [2_500_000.1, 3.132512, -5.5]
+        );
+    }
+
+    #[test]
+    fn test_bounding_box_does_not_include_origin() {
+        let points = vec![
+            Point3::from_array([5000.0, 200.0, 1.0]),
+            Point3::from_array([5002.0, 204.0, -3.0]),
+        ];
+        let bounding_box = BoundingBox::new(&points);
+        assert_eq!(
+            bounding_box.get_center(),
+            Point3::from_array([5001.0, 202.0, -1.0])
+        );
+        assert_eq!(bounding_box.get_bounding_radius(), 3.0);
+    }
+
     #[test]
     fn test_get_longest_distance_from_point() {
         let furthest_point = Point3 {
@@ -412,4 +729,25 @@ mod tests {
             distance(&origin, &furthest_point),
         );
     }
+
+    #[test]
+    fn test_face_data_stays_aligned_with_triangles() {
+        let mut mesh = Mesh::new();
+        for _ in 0..3 {
+            mesh.push_triangle([0, 1, 2], Point3::new());
+        }
+        mesh.set_group(1, 4);
+        mesh.set_uv_indices(2, [5, 6, 7]);
+        mesh.push_triangle([2, 1, 0], Point3::new());
+        assert_eq!(mesh.face_data.groups.as_ref().unwrap().len(), 4);
+        assert_eq!(mesh.group(0), None);
+        assert_eq!(mesh.material(1), None);
+
+        mesh.flip_winding(2);
+        mesh.retain_triangles(&[false, true, true, true]);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 1], [2, 1, 0]]);
+        assert_eq!(mesh.group(0), Some(4));
+        assert_eq!(mesh.uv_indices(1), Some([5, 7, 6]));
+        assert_eq!(mesh.uv_indices(2), None);
+    }
 }
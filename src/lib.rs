@@ -1,2 +1,3 @@
 pub mod general;
+pub mod mesh_processing;
 pub mod renderer;
@@ -1,28 +1,290 @@
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use crossterm::event::Event;
 use crossterm::terminal;
-use rust_obj_terminal_viewer::general::positions_3d::{BoundingBox, Point as Point3};
+use rust_obj_terminal_viewer::general::positions_3d::{
+    BoundingBox, Degrees, GlobalPoint, Mesh, Point as Point3,
+};
+use rust_obj_terminal_viewer::mesh_processing::axis_conversion::{Axis, LengthUnit};
+use rust_obj_terminal_viewer::mesh_processing::decimation::DecimationTarget;
+use rust_obj_terminal_viewer::mesh_processing::deviation::Deviation;
+use rust_obj_terminal_viewer::mesh_processing::section::Plane;
+use rust_obj_terminal_viewer::mesh_processing::statistics::MeshStatistics;
+use rust_obj_terminal_viewer::mesh_processing::subdivision::SubdivisionScheme;
 use rust_obj_terminal_viewer::renderer::camera_rotation::CameraInputHelper;
+use rust_obj_terminal_viewer::renderer::explode_input::ExplodeInputHelper;
 use rust_obj_terminal_viewer::renderer::interface::Renderer;
 use rust_obj_terminal_viewer::renderer::obj_parser::ObjParser;
+use rust_obj_terminal_viewer::renderer::pick_input::PickInputHelper;
+use rust_obj_terminal_viewer::renderer::pipeline::ambient_occlusion::AmbientOcclusion;
+use rust_obj_terminal_viewer::renderer::pipeline::fragment_shader::{
+    BlinnPhongShader, VertexColorShader,
+};
+use rust_obj_terminal_viewer::renderer::pipeline::light::{Light, LightKind};
+use rust_obj_terminal_viewer::renderer::render::Shading;
+use rust_obj_terminal_viewer::renderer::section_input::SectionInputHelper;
+use rust_obj_terminal_viewer::renderer::subdivision_input::SubdivisionInputHelper;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
 
 const SHORT_ABOUT_TEXT: &str =
     "A CLI program to view 3D models directly in the terminal. Supports .obj files.";
+/// Meshes with more triangles than this are replaced by a simplified version while rotating
+const LOD_TRIANGLE_COUNT: usize = 20_000;
 
 #[derive(Parser)]
 #[command(
     about = SHORT_ABOUT_TEXT,
     long_about = format!("{SHORT_ABOUT_TEXT}\n\n\
     To rotate the object, move the mouse with the left mouse button pressed. \
-    To close the program, press `q`.")
+    To close the program, press `q`."),
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Cli {
-    path: std::path::PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    view_args: ViewArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print statistics and a topology report of a mesh, without opening the viewer
+    Info(InfoArgs),
+    /// Cut the mesh with a plane and write the contours of the cut to an SVG or DXF file
+    Section(SectionArgs),
+    /// Measure how far the surface of a mesh is from a reference mesh, for example a scan from a CAD model
+    Diff(DiffArgs),
+}
+
+#[derive(Args)]
+struct ViewArgs {
+    #[arg(required = true)]
+    path: Option<PathBuf>,
+
+    #[command(flatten)]
+    mesh_args: MeshArgs,
+
+    #[command(flatten)]
+    view_options: ViewOptions,
+}
+
+#[derive(Args, Default)]
+struct ViewOptions {
+    /// Always render the full mesh, also while rotating the camera
+    #[arg(long)]
+    no_lod: bool,
+
+    /// Rotate the camera around the world origin instead of around the center of the model
+    #[arg(long)]
+    orbit_origin: bool,
+
+    /// Start with a section plane perpendicular to AXIS.
+    /// Press s to switch between axes and [ or ] to move the plane
+    #[arg(long, value_name = "AXIS", allow_hyphen_values = true)]
+    section: Option<Axis>,
+
+    /// Position of the section plane along its axis, the center of the model by default
+    #[arg(long, value_name = "POSITION", allow_hyphen_values = true)]
+    section_position: Option<f64>,
+
+    /// Render FACTOR × FACTOR samples per character and average them,
+    /// so that thin parts don't flicker while rotating. Slower for larger factors
+    #[arg(long, value_name = "FACTOR", default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=8))]
+    supersampling: u8,
+
+    /// flat, gouraud or phong (the default). Smooth shading uses the vertex normals from the file,
+    /// or generates them for meshes without vertex normals
+    #[arg(long, value_name = "MODE")]
+    shading: Option<Shading>,
+
+    /// Edges sharper than DEGREES stay hard when vertex normals are generated, 45 by default
+    #[arg(long, value_name = "DEGREES")]
+    crease_angle: Option<f32>,
+
+    /// Add a light instead of the default one, can be repeated. One of
+    /// directional:X,Y,Z (the direction it shines along), point:X,Y,Z:RANGE (brightness halves at RANGE)
+    /// or headlight, each followed by an optional :INTENSITY and :R,G,B color
+    #[arg(long = "light", value_name = "LIGHT", allow_hyphen_values = true)]
+    lights: Vec<Light>,
+
+    /// Start with shadows from directional lights, press h to toggle them
+    #[arg(long)]
+    shadows: bool,
+
+    /// Darken crevices and the areas where parts touch (screen-space ambient occlusion)
+    #[arg(long)]
+    ambient_occlusion: bool,
+
+    /// How far around each character crevices are searched for, in characters
+    #[arg(long, value_name = "CHARS", requires = "ambient_occlusion")]
+    ao_radius: Option<f32>,
+
+    /// How much the deepest crevices are darkened, from 0 to 1
+    #[arg(long, value_name = "STRENGTH", requires = "ambient_occlusion")]
+    ao_strength: Option<f32>,
+}
+
+#[derive(Args)]
+struct InfoArgs {
+    path: PathBuf,
+
+    #[command(flatten)]
+    mesh_args: MeshArgs,
+
+    /// Use json to read the report from other programs
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+/// Options that change the mesh after it has been loaded.
+/// They are applied in the order that they are declared here.
+#[derive(Args)]
+struct MeshArgs {
+    /// The axis that points up in the file, for example +z for many CAD programs
+    #[arg(long, value_name = "AXIS", allow_hyphen_values = true)]
+    up_axis: Option<Axis>,
+
+    /// Mirror the mesh, to convert from a left-handed coordinate system
+    #[arg(long)]
+    flip_handedness: bool,
+
+    /// The length unit used in the file (mm, cm, m or in), the mesh is converted to metres
+    #[arg(long)]
+    unit: Option<LengthUnit>,
+
+    /// Scale the mesh by one FACTOR, or by a separate factor per axis given as x,y,z
+    #[arg(long, value_name = "FACTOR", value_parser = parse_scale, allow_hyphen_values = true)]
+    scale: Option<Point3>,
+
+    /// Rotate the mesh around an axis through the origin, given as x,y,z,degrees
+    #[arg(long, value_name = "X,Y,Z,DEGREES", value_parser = parse_numbers::<4>, allow_hyphen_values = true)]
+    rotate: Option<[f32; 4]>,
+
+    /// Move the mesh by an offset given as x,y,z
+    #[arg(long, value_name = "X,Y,Z", value_parser = parse_numbers::<3>, allow_hyphen_values = true)]
+    translate: Option<[f32; 3]>,
+
+    /// Merge vertices that are closer to each other than TOLERANCE
+    #[arg(long, value_name = "TOLERANCE")]
+    weld: Option<f32>,
+
+    /// Remove broken triangles and make the winding order of all triangles consistent
+    #[arg(long)]
+    repair: bool,
+
+    /// Smooth the mesh by subdividing it LEVELS times
+    #[arg(long, value_name = "LEVELS")]
+    subdivide: Option<u32>,
+
+    /// loop for triangle meshes or catmull-clark for quad meshes, chosen from the mesh by default.
+    /// Also used when subdividing in the viewer with + and -
+    #[arg(long, value_name = "SCHEME")]
+    subdivision_scheme: Option<SubdivisionScheme>,
+
+    /// Simplify the mesh to a triangle count (e.g. 5000) or to a ratio of its triangles (e.g. 0.25)
+    #[arg(long, value_name = "TARGET")]
+    decimate: Option<DecimationTarget>,
+}
+
+#[derive(Args)]
+struct SectionArgs {
+    path: PathBuf,
+
+    #[command(flatten)]
+    mesh_args: MeshArgs,
+
+    /// The axis that the section plane is perpendicular to
+    #[arg(long, default_value = "y", allow_hyphen_values = true)]
+    axis: Axis,
+
+    /// Position of the plane along the axis, the center of the model by default
+    #[arg(long, allow_hyphen_values = true)]
+    position: Option<f64>,
+
+    /// The file to write, its extension (.svg or .dxf) decides the format
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// The mesh that is measured
+    path: PathBuf,
+
+    /// The mesh that it is compared to
+    reference_path: PathBuf,
+
+    /// Applied to both meshes
+    #[command(flatten)]
+    mesh_args: MeshArgs,
+
+    /// Fail if the meshes are further apart than TOLERANCE anywhere.
+    /// Also the distance that is shown brightest in the viewer
+    #[arg(long, value_name = "TOLERANCE")]
+    tolerance: Option<f32>,
+
+    /// Show the measured mesh in the viewer, brighter where it is further from the reference mesh
+    #[arg(long)]
+    view: bool,
+
+    /// Use json to read the distances from other programs
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
 }
 
-// +x is to the right, +y is up, -z is forwards
 fn main() {
     let args = Cli::parse();
-    let obj_path = args.path;
+    match args.command {
+        Some(Command::Info(info_args)) => print_info(&info_args),
+        Some(Command::Section(section_args)) => export_section(&section_args),
+        Some(Command::Diff(diff_args)) => print_diff(&diff_args),
+        None => view(&args.view_args),
+    }
+}
+
+// +x is to the right, +y is up, -z is forwards
+fn view(args: &ViewArgs) {
+    let obj_path = args.path.as_ref().expect("path is a required argument");
+    let (mesh, info_texts) = match load_mesh(obj_path, &args.mesh_args) {
+        Ok(loaded) => loaded,
+        Err(message) => {
+            println!("{message}");
+            return;
+        }
+    };
+    let subdivision_scheme = args
+        .mesh_args
+        .subdivision_scheme
+        .unwrap_or_else(|| SubdivisionScheme::for_mesh(&mesh));
+    show_mesh(
+        mesh,
+        info_texts,
+        subdivision_scheme,
+        None,
+        &args.view_options,
+    );
+}
+
+/// Opens the viewer until the user exits it.
+/// `vertex_brightness` is shown instead of the lighting if given, with one value for every point of the mesh.
+fn show_mesh(
+    mesh: Mesh,
+    mut info_texts: Vec<String>,
+    subdivision_scheme: SubdivisionScheme,
+    vertex_brightness: Option<Vec<f32>>,
+    args: &ViewOptions,
+) {
     let terminal_size = terminal::size().unwrap();
     let mut renderer = Renderer::new(
         terminal_size.0,
@@ -32,29 +294,383 @@ fn main() {
         70.0,
         " .'`^\",:;Il!i><~+_-?][}{1)(|\\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$",
     );
-    let mesh = match ObjParser::parse_file(&obj_path) {
-        Ok(mesh) => mesh,
-        Err(message) => {
-            let path_string = obj_path.to_str().unwrap();
-            println!("Error when parsing {path_string}: {message}");
-            return;
-        }
+    let lod_receiver = if !args.no_lod && mesh.triangle_count() > LOD_TRIANGLE_COUNT {
+        Some(decimate_in_background(&mesh, LOD_TRIANGLE_COUNT))
+    } else {
+        None
+    };
+    renderer.set_mesh(mesh.clone());
+    renderer.shader = if vertex_brightness.is_some() {
+        Box::new(VertexColorShader)
+    } else {
+        Box::new(BlinnPhongShader::new(&mesh.materials))
     };
-    renderer.set_mesh(mesh);
-    renderer.info_text = Some("press q to exit".to_owned());
+    renderer.vertex_colors = vertex_brightness;
+    renderer.supersampling = args.supersampling;
+    if let Some(shading) = args.shading {
+        renderer.shading = shading;
+    }
+    if let Some(crease_angle) = args.crease_angle {
+        renderer.crease_angle = Degrees(crease_angle);
+    }
+    renderer.shadows = args.shadows;
+    if args.ambient_occlusion {
+        let default = AmbientOcclusion::default();
+        renderer.ambient_occlusion = Some(AmbientOcclusion {
+            radius: args.ao_radius.unwrap_or(default.radius),
+            strength: args.ao_strength.unwrap_or(default.strength),
+        });
+    }
+    if !args.lights.is_empty() {
+        renderer.lights = args
+            .lights
+            .iter()
+            .map(|light| local_light(light, &mesh.origin))
+            .collect();
+    }
+    let mut subdivision_input_helper = SubdivisionInputHelper::new(mesh, subdivision_scheme);
+    let mut lod_mesh = None;
+    info_texts.push("press q to exit".to_owned());
+    renderer.info_text = Some(info_texts.join(" | "));
 
-    let radius =
-        BoundingBox::new(&renderer.mesh.points).get_longest_distance_from_point(&Point3::new());
+    let pivot = if args.orbit_origin {
+        // the origin of the file, which is far away from the points if the mesh was rebased
+        renderer
+            .mesh
+            .origin
+            .local_offset_to(&GlobalPoint::default())
+    } else {
+        BoundingBox::new(&renderer.mesh.points).get_center()
+    };
+    let radius = BoundingBox::new(&renderer.mesh.points).get_longest_distance_from_point(&pivot);
     let camera_distance = renderer.camera.distance_to_fit_sphere(radius);
     let mut camera_input_helper =
         CameraInputHelper::new(terminal_size.0, terminal_size.1, camera_distance);
+    camera_input_helper.set_pivot(pivot);
     renderer.camera.far = camera_distance + radius;
     renderer.camera.near = camera_distance - radius;
 
-    let mut frame_loop = |renderer: &mut Renderer, events| {
+    let bounding_box = BoundingBox::new(&renderer.mesh.points);
+    let [min, max] = bounding_box.get_min_max();
+    let longest_side = max
+        .relative_to(&min)
+        .to_array()
+        .into_iter()
+        .fold(0.0, f32::max);
+    let mut section_input_helper = SectionInputHelper::new(
+        bounding_box.get_center(),
+        longest_side / 50.0,
+        renderer.mesh.origin,
+    );
+    if let Some(axis) = args.section {
+        section_input_helper.show(axis, args.section_position);
+    }
+    let mut pick_input_helper = PickInputHelper::new(renderer.mesh.origin);
+    let mut explode_input_helper = ExplodeInputHelper::new();
+    let base_info_text = renderer.info_text.clone().unwrap_or_default();
+
+    let mut frame_loop = |renderer: &mut Renderer, events: Vec<Event>| {
+        subdivision_input_helper.process_input_events(&events);
+        let subdivision_has_changed = subdivision_input_helper.take_has_changed();
+        let subdivision_description_has_changed =
+            subdivision_input_helper.take_description_has_changed();
+        if subdivision_has_changed {
+            renderer.set_mesh(subdivision_input_helper.current_mesh().clone());
+            if subdivision_input_helper.current_level() == 0 {
+                renderer.set_lod_mesh(lod_mesh.clone());
+            }
+            // the points, picking and overlay lines are updated below as if the explosion changed
+            explode_input_helper.mesh_changed();
+        }
+
+        explode_input_helper.process_input_events(&events);
+        let explode_has_changed = explode_input_helper.take_has_changed();
+        if explode_has_changed {
+            renderer.set_mesh_points(
+                explode_input_helper.exploded_points(subdivision_input_helper.current_mesh()),
+            );
+            pick_input_helper.mesh_changed();
+        }
+
+        section_input_helper.process_input_events(&events);
+        pick_input_helper.process_input_events(
+            &events,
+            &renderer.mesh,
+            &renderer.camera,
+            renderer.char_buffer.width,
+            renderer.char_buffer.height,
+        );
+        if let Some(pivot) = pick_input_helper.take_pivot() {
+            camera_input_helper.set_pivot(pivot);
+        }
+        let section_has_changed = section_input_helper.take_has_changed();
+        let pick_has_changed = pick_input_helper.take_has_changed();
+        if section_has_changed
+            || pick_has_changed
+            || explode_has_changed
+            || subdivision_description_has_changed
+        {
+            renderer.overlay_lines = match section_input_helper.plane() {
+                Some(plane) => renderer.mesh.section(&plane).lines(),
+                None => Vec::new(),
+            };
+            renderer
+                .overlay_lines
+                .extend(pick_input_helper.measurement_line());
+            let descriptions = [
+                pick_input_helper.description(),
+                explode_input_helper.description(),
+                section_input_helper.description(),
+                subdivision_input_helper.description(),
+                Some(base_info_text.clone()),
+            ];
+            renderer.info_text = Some(
+                descriptions
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            );
+        }
+
         camera_input_helper.process_input_events(events);
         camera_input_helper.apply_to_camera(&mut renderer.camera);
+        if let Some(Ok(received_mesh)) = lod_receiver.as_ref().map(Receiver::try_recv) {
+            // the simplified mesh is only made for the original mesh
+            if subdivision_input_helper.current_level() == 0 {
+                renderer.set_lod_mesh(Some(received_mesh.clone()));
+            }
+            lod_mesh = Some(received_mesh);
+        }
+        // the simplified mesh is not exploded
+        renderer.use_lod_mesh =
+            camera_input_helper.is_rotating() && !explode_input_helper.is_exploded();
     };
 
     renderer.start_rendering(&mut frame_loop);
 }
+
+fn print_info(args: &InfoArgs) {
+    let (mesh, _) = match load_mesh(&args.path, &args.mesh_args) {
+        Ok(loaded) => loaded,
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(1);
+        }
+    };
+    let statistics = MeshStatistics::new(&mesh);
+    match args.format {
+        OutputFormat::Text => println!("{}", statistics.to_text()),
+        OutputFormat::Json => println!("{}", statistics.to_json()),
+    }
+}
+
+fn print_diff(args: &DiffArgs) {
+    let [(mesh, info_texts), (reference_mesh, _)] =
+        [&args.path, &args.reference_path].map(|path| match load_mesh(path, &args.mesh_args) {
+            Ok(loaded) => loaded,
+            Err(message) => {
+                eprintln!("{message}");
+                process::exit(1);
+            }
+        });
+    let deviation = match Deviation::new(&mesh, &reference_mesh) {
+        Ok(deviation) => deviation,
+        Err(message) => {
+            eprintln!("Can not compare the meshes: {message}");
+            process::exit(1);
+        }
+    };
+    match args.format {
+        OutputFormat::Text => println!("{}", deviation.to_text()),
+        OutputFormat::Json => println!("{}", deviation.to_json()),
+    }
+
+    if args.view {
+        let scale = args.tolerance.unwrap_or(deviation.max_distance);
+        let mut info_texts = info_texts;
+        info_texts.push(format!(
+            "hausdorff distance: {}",
+            deviation.hausdorff_distance
+        ));
+        let subdivision_scheme = args
+            .mesh_args
+            .subdivision_scheme
+            .unwrap_or_else(|| SubdivisionScheme::for_mesh(&mesh));
+        let options = ViewOptions {
+            // the simplified mesh has other points than the distances
+            no_lod: true,
+            ..ViewOptions::default()
+        };
+        show_mesh(
+            mesh,
+            info_texts,
+            subdivision_scheme,
+            Some(deviation.brightness(scale)),
+            &options,
+        );
+    }
+
+    if let Some(tolerance) = args.tolerance {
+        if deviation.hausdorff_distance > tolerance {
+            eprintln!(
+                "The meshes are up to {} apart, more than the tolerance of {tolerance}",
+                deviation.hausdorff_distance
+            );
+            process::exit(1);
+        }
+    }
+}
+
+fn export_section(args: &SectionArgs) {
+    let (mesh, _) = match load_mesh(&args.path, &args.mesh_args) {
+        Ok(loaded) => loaded,
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(1);
+        }
+    };
+    let normal = args.axis.direction();
+    let point = match args.position {
+        Some(position) => {
+            let local_position = (position - mesh.origin.dot_product(&normal)) as f32;
+            normal.map(|component| component * local_position)
+        }
+        None => BoundingBox::new(&mesh.points).get_center(),
+    };
+    let section = mesh.section(&Plane { point, normal });
+
+    let extension = args
+        .output
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let content = match extension.map(str::to_lowercase).as_deref() {
+        Some("svg") => section.to_svg(),
+        Some("dxf") => section.to_dxf(),
+        _ => {
+            eprintln!("The output file has to end with .svg or .dxf");
+            process::exit(1);
+        }
+    };
+    if let Err(error) = fs::write(&args.output, content) {
+        eprintln!("Error when writing {}: {error}", args.output.display());
+        process::exit(1);
+    }
+
+    let open_contour_count = section
+        .contours
+        .iter()
+        .filter(|contour| !contour.is_closed)
+        .count();
+    println!(
+        "contours: {} ({open_contour_count} open)\ntotal length: {}",
+        section.contours.len(),
+        section.length()
+    );
+}
+
+/// Moves point lights from the coordinates of the file to the coordinates of the mesh, which may have been rebased
+fn local_light(light: &Light, origin: &GlobalPoint) -> Light {
+    let mut light = light.clone();
+    if let LightKind::Point { position, .. } = &mut light.kind {
+        let global_position = GlobalPoint::from_array(position.to_array().map(f64::from));
+        *position = origin.local_offset_to(&global_position);
+    }
+    light
+}
+
+/// Parses the file and applies the mesh options to it.
+/// Also returns short descriptions of what the options did, to show to the user.
+fn load_mesh(obj_path: &PathBuf, args: &MeshArgs) -> Result<(Mesh, Vec<String>), String> {
+    let mut mesh = ObjParser::parse_file(obj_path).map_err(|message| {
+        let path_string = obj_path.to_str().unwrap();
+        format!("Error when parsing {path_string}: {message}")
+    })?;
+    let mut descriptions = Vec::new();
+    if let Some(up_axis) = args.up_axis {
+        mesh.convert_up_axis(up_axis);
+    }
+    if args.flip_handedness {
+        mesh.flip_handedness();
+    }
+    if let Some(unit) = args.unit {
+        mesh.convert_unit(unit, LengthUnit::Metre);
+    }
+    if let Some(factors) = &args.scale {
+        mesh.scale(factors)
+            .map_err(|message| format!("Can not scale the mesh: {message}"))?;
+    }
+    if let Some([x, y, z, degrees]) = args.rotate {
+        mesh.rotate(&Point3::from_array([x, y, z]), Degrees(degrees));
+    }
+    if let Some(offset) = args.translate {
+        mesh.translate(&Point3::from_array(offset));
+    }
+    if let Some(tolerance) = args.weld {
+        let merged_vertices = mesh.weld_vertices(tolerance);
+        descriptions.push(format!("welded {merged_vertices} vertices"));
+    }
+    if args.repair {
+        let report = mesh.repair();
+        let removed_triangles = report.removed_triangles_with_invalid_vertices
+            + report.removed_degenerate_triangles
+            + report.removed_duplicate_triangles;
+        descriptions.push(format!(
+            "repair removed {removed_triangles} and flipped {} triangles",
+            report.flipped_triangles
+        ));
+    }
+    if let Some(levels) = args.subdivide {
+        let scheme = args
+            .subdivision_scheme
+            .unwrap_or_else(|| SubdivisionScheme::for_mesh(&mesh));
+        for _ in 0..levels {
+            mesh.subdivide(scheme);
+        }
+        descriptions.push(format!("subdivided {levels} times"));
+    }
+    if let Some(target) = args.decimate {
+        mesh.decimate(target);
+        let triangle_count = mesh.triangle_count();
+        descriptions.push(format!("decimated to {triangle_count} triangles"));
+    }
+    if mesh.origin != GlobalPoint::default() {
+        let GlobalPoint { x, y, z } = mesh.origin;
+        descriptions.push(format!("coordinates relative to {x} {y} {z}"));
+    }
+    Ok((mesh, descriptions))
+}
+
+/// Parses comma separated numbers like "1,2.5,-3"
+fn parse_numbers<const N: usize>(string: &str) -> Result<[f32; N], String> {
+    let numbers = string
+        .split(',')
+        .map(|number| number.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|error| error.to_string())?;
+    numbers
+        .try_into()
+        .map_err(|_| format!("expected {N} comma separated numbers"))
+}
+
+/// Parses either one factor for all axes, or one factor per axis
+fn parse_scale(string: &str) -> Result<Point3, String> {
+    match parse_numbers::<1>(string) {
+        Ok([factor]) => Ok(Point3::from_array([factor; 3])),
+        Err(_) => parse_numbers::<3>(string)
+            .map(Point3::from_array)
+            .map_err(|_| String::from("expected one number or 3 comma separated numbers")),
+    }
+}
+
+/// Decimates a copy of the mesh on another thread, so that the viewer can start right away
+fn decimate_in_background(mesh: &Mesh, triangle_count: usize) -> Receiver<Mesh> {
+    let mut lod_mesh = mesh.clone();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        lod_mesh.decimate(DecimationTarget::TriangleCount(triangle_count));
+        // the viewer might already have been closed, then nobody needs the result
+        let _ = sender.send(lod_mesh);
+    });
+    receiver
+}
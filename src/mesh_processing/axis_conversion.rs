@@ -0,0 +1,204 @@
+use crate::general::positions_3d::{Mesh, Point as Point3};
+use std::str::FromStr;
+
+/// A direction along one of the coordinate axes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    /// Parses strings like "+z", "z" and "-y"
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "+x" | "x" => Ok(Axis::PositiveX),
+            "-x" => Ok(Axis::NegativeX),
+            "+y" | "y" => Ok(Axis::PositiveY),
+            "-y" => Ok(Axis::NegativeY),
+            "+z" | "z" => Ok(Axis::PositiveZ),
+            "-z" => Ok(Axis::NegativeZ),
+            _ => Err(format!(
+                "'{string}' is not an axis, use for example +z or -y"
+            )),
+        }
+    }
+}
+
+/// A unit of length that mesh coordinates can be given in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Millimetre,
+    Centimetre,
+    Metre,
+    Inch,
+}
+
+impl FromStr for LengthUnit {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "mm" => Ok(LengthUnit::Millimetre),
+            "cm" => Ok(LengthUnit::Centimetre),
+            "m" => Ok(LengthUnit::Metre),
+            "in" => Ok(LengthUnit::Inch),
+            _ => Err(format!("'{string}' is not a unit, use mm, cm, m or in")),
+        }
+    }
+}
+
+impl LengthUnit {
+    pub fn in_metres(&self) -> f32 {
+        match self {
+            LengthUnit::Millimetre => 0.001,
+            LengthUnit::Centimetre => 0.01,
+            LengthUnit::Metre => 1.0,
+            LengthUnit::Inch => 0.0254,
+        }
+    }
+}
+
+impl Axis {
+    /// A unit vector pointing along the axis
+    pub fn direction(&self) -> Point3 {
+        let direction = match self {
+            Axis::PositiveX => [1.0, 0.0, 0.0],
+            Axis::NegativeX => [-1.0, 0.0, 0.0],
+            Axis::PositiveY => [0.0, 1.0, 0.0],
+            Axis::NegativeY => [0.0, -1.0, 0.0],
+            Axis::PositiveZ => [0.0, 0.0, 1.0],
+            Axis::NegativeZ => [0.0, 0.0, -1.0],
+        };
+        Point3::from_array(direction)
+    }
+}
+
+impl Mesh {
+    /// Rotates the mesh so that `up_axis` points along +Y, which is up in the viewer.
+    ///
+    /// For Z-up meshes the +Y axis of the file ends up pointing along -Z,
+    /// which is the forward direction of the viewer.
+    ///
+    /// # Example
+    /// ```
+    /// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+    /// use rust_obj_terminal_viewer::mesh_processing::axis_conversion::Axis;
+    ///
+    /// let mut mesh = Mesh::new();
+    /// mesh.points.push(Point3::from_array([1.0, 2.0, 3.0]));
+    /// mesh.convert_up_axis(Axis::PositiveZ);
+    /// assert_eq!(mesh.points[0], Point3::from_array([1.0, 3.0, -2.0]));
+    /// ```
+    pub fn convert_up_axis(&mut self, up_axis: Axis) {
+        // all of these are rotations, so the winding order stays correct
+        let rotate: fn(&Point3) -> Point3 = match up_axis {
+            Axis::PositiveY => return,
+            Axis::NegativeY => |p| Point3::from_array([p.x, -p.y, -p.z]),
+            Axis::PositiveZ => |p| Point3::from_array([p.x, p.z, -p.y]),
+            Axis::NegativeZ => |p| Point3::from_array([p.x, -p.z, p.y]),
+            Axis::PositiveX => |p| Point3::from_array([-p.y, p.x, p.z]),
+            Axis::NegativeX => |p| Point3::from_array([p.y, -p.x, p.z]),
+        };
+        self.map_points_and_normals(rotate);
+    }
+
+    /// Mirrors the mesh along the Z axis, which converts between
+    /// left-handed and right-handed coordinate systems.
+    /// The winding order is reversed, so that the triangles keep facing the same way.
+    pub fn flip_handedness(&mut self) {
+        self.map_points_and_normals(|p| Point3::from_array([p.x, p.y, -p.z]));
+        for triangle in 0..self.triangle_count() {
+            self.flip_winding(triangle);
+        }
+    }
+
+    /// Scales the mesh, so that coordinates given in `from` are given in `to` instead.
+    pub fn convert_unit(&mut self, from: LengthUnit, to: LengthUnit) {
+        let scale = from.in_metres() / to.in_metres();
+        for point in &mut self.points {
+            *point = point.map(|component| component * scale);
+        }
+        self.origin = self
+            .origin
+            .map_linear(|point| point.map(|component| component * scale));
+    }
+
+    fn map_points_and_normals(&mut self, f: fn(&Point3) -> Point3) {
+        let points = self.points.iter_mut();
+        for point in points
+            .chain(&mut self.normals)
+            .chain(&mut self.face_normals)
+        {
+            *point = f(point);
+        }
+        self.origin = self.origin.map_linear(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Axis, LengthUnit};
+    use crate::general::positions_3d::{Mesh, Point as Point3, Triangle as Triangle3};
+
+    fn triangle_mesh() -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.0, 0.0, 0.0]),
+            Point3::from_array([1.0, 0.0, 0.0]),
+            Point3::from_array([0.0, 0.0, -1.0]),
+        ];
+        mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 1.0, 0.0]));
+        mesh
+    }
+
+    fn normal_matches_winding(mesh: &Mesh) -> bool {
+        Triangle3::get_normal(&mesh.triangle_points(0)) == mesh.face_normals[0]
+    }
+
+    #[test]
+    fn test_every_up_axis_ends_up_as_y() {
+        let axes = [
+            (Axis::PositiveX, [1.0, 0.0, 0.0]),
+            (Axis::NegativeX, [-1.0, 0.0, 0.0]),
+            (Axis::PositiveY, [0.0, 1.0, 0.0]),
+            (Axis::NegativeY, [0.0, -1.0, 0.0]),
+            (Axis::PositiveZ, [0.0, 0.0, 1.0]),
+            (Axis::NegativeZ, [0.0, 0.0, -1.0]),
+        ];
+        for (axis, direction) in axes {
+            let mut mesh = Mesh::new();
+            mesh.points.push(Point3::from_array(direction));
+            mesh.convert_up_axis(axis);
+            assert_eq!(mesh.points[0], Point3::from_array([0.0, 1.0, 0.0]));
+        }
+    }
+
+    #[test]
+    fn test_up_axis_conversion_keeps_winding_consistent() {
+        let mut mesh = triangle_mesh();
+        mesh.convert_up_axis(Axis::PositiveZ);
+        assert!(normal_matches_winding(&mesh));
+    }
+
+    #[test]
+    fn test_flip_handedness_keeps_winding_consistent() {
+        let mut mesh = triangle_mesh();
+        mesh.flip_handedness();
+        assert_eq!(mesh.points[2], Point3::from_array([0.0, 0.0, 1.0]));
+        assert!(normal_matches_winding(&mesh));
+    }
+
+    #[test]
+    fn test_convert_unit() {
+        let mut mesh = triangle_mesh();
+        mesh.convert_unit(LengthUnit::Millimetre, LengthUnit::Centimetre);
+        assert!((mesh.points[1].x - 0.1).abs() < 1e-6);
+    }
+}
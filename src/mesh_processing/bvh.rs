@@ -0,0 +1,610 @@
+use crate::general::positions_3d::{
+    cross_product, dot_product, BoundingBox, Mesh, Point as Point3,
+};
+use crate::mesh_processing::section::Plane;
+
+/// Nodes are split until they hold at most this many triangles
+const MAX_LEAF_SIZE: usize = 4;
+
+/// A half-line that starts at `origin`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: Point3,
+    /// Does not need to be a unit vector, distances along the ray are measured in multiples of its length
+    pub direction: Point3,
+}
+
+impl Ray {
+    pub fn point_at(&self, distance: f32) -> Point3 {
+        self.origin
+            .add(&self.direction.map(|component| component * distance))
+    }
+}
+
+/// The closest triangle hit by a ray
+#[derive(Debug, Clone, PartialEq)]
+pub struct RayHit {
+    /// Index of the triangle in the mesh
+    pub triangle: usize,
+    /// Position along the ray, in multiples of `Ray::direction`
+    pub distance: f32,
+    pub point: Point3,
+}
+
+/// The point on the mesh surface that is closest to a query point
+#[derive(Debug, Clone, PartialEq)]
+pub struct NearestPoint {
+    /// Index of the triangle in the mesh
+    pub triangle: usize,
+    pub point: Point3,
+    pub distance: f32,
+}
+
+/// How an axis aligned box relates to a query volume
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overlap {
+    Outside,
+    Partial,
+    Inside,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: [f32; 3],
+    max: [f32; 3],
+}
+
+impl Bounds {
+    fn empty() -> Self {
+        Bounds {
+            min: [f32::INFINITY; 3],
+            max: [f32::NEG_INFINITY; 3],
+        }
+    }
+
+    fn from_triangle(triangle: &[Point3; 3]) -> Self {
+        triangle.iter().fold(Bounds::empty(), |bounds, point| {
+            bounds.expanded(&point.to_array())
+        })
+    }
+
+    fn expanded(&self, point: &[f32; 3]) -> Self {
+        Bounds {
+            min: [0, 1, 2].map(|axis| self.min[axis].min(point[axis])),
+            max: [0, 1, 2].map(|axis| self.max[axis].max(point[axis])),
+        }
+    }
+
+    fn union(&self, other: &Bounds) -> Self {
+        self.expanded(&other.min).expanded(&other.max)
+    }
+
+    fn center(&self) -> [f32; 3] {
+        [0, 1, 2].map(|axis| (self.min[axis] + self.max[axis]) * 0.5)
+    }
+
+    fn longest_axis(&self) -> usize {
+        let size = [0, 1, 2].map(|axis| self.max[axis] - self.min[axis]);
+        (0..3)
+            .max_by(|&a, &b| size[a].total_cmp(&size[b]))
+            .unwrap_or(0)
+    }
+
+    /// Position where the ray enters the box, if it does so before `max_distance`
+    fn ray_distance(
+        &self,
+        origin: &[f32; 3],
+        inverse_direction: &[f32; 3],
+        max_distance: f32,
+    ) -> Option<f32> {
+        let mut near: f32 = 0.0;
+        let mut far = max_distance;
+        for axis in 0..3 {
+            let t1 = (self.min[axis] - origin[axis]) * inverse_direction[axis];
+            let t2 = (self.max[axis] - origin[axis]) * inverse_direction[axis];
+            near = near.max(t1.min(t2));
+            far = far.min(t1.max(t2));
+        }
+        (near <= far).then_some(near)
+    }
+
+    fn distance_squared(&self, point: &[f32; 3]) -> f32 {
+        (0..3)
+            .map(|axis| {
+                let outside = (self.min[axis] - point[axis]).max(point[axis] - self.max[axis]);
+                outside.max(0.0).powi(2)
+            })
+            .sum()
+    }
+
+    fn overlap_with_box(&self, other: &Bounds) -> Overlap {
+        if (0..3).any(|axis| self.max[axis] < other.min[axis] || self.min[axis] > other.max[axis]) {
+            Overlap::Outside
+        } else if (0..3)
+            .all(|axis| self.min[axis] >= other.min[axis] && self.max[axis] <= other.max[axis])
+        {
+            Overlap::Inside
+        } else {
+            Overlap::Partial
+        }
+    }
+
+    /// `planes` face inwards
+    fn overlap_with_planes(&self, planes: &[Plane]) -> Overlap {
+        let mut overlap = Overlap::Inside;
+        for plane in planes {
+            // the corners that are furthest in front of and behind the plane
+            let normal = plane.normal.to_array();
+            let corner = |towards_normal: bool| {
+                Point3::from_array([0, 1, 2].map(|axis| {
+                    if (normal[axis] >= 0.0) == towards_normal {
+                        self.max[axis]
+                    } else {
+                        self.min[axis]
+                    }
+                }))
+            };
+            if plane.signed_distance(&corner(true)) < 0.0 {
+                return Overlap::Outside;
+            }
+            if plane.signed_distance(&corner(false)) < 0.0 {
+                overlap = Overlap::Partial;
+            }
+        }
+        overlap
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    bounds: Bounds,
+    /// For leaves the position of the first triangle in `Bvh::triangles`.
+    /// For inner nodes the index of the second child, the first child directly follows its parent.
+    start: u32,
+    /// Zero for inner nodes
+    count: u32,
+}
+
+struct BuildTriangle {
+    index: u32,
+    bounds: Bounds,
+    center: [f32; 3],
+}
+
+/// Bounding volume hierarchy over the triangles of a mesh, to find the triangles hit by a ray,
+/// close to a point or inside a volume without testing every triangle.
+///
+/// The triangles are copied, so the hierarchy has to be built again when the mesh changes.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+/// use rust_obj_terminal_viewer::mesh_processing::bvh::{Bvh, Ray};
+///
+/// let mut mesh = Mesh::new();
+/// mesh.points = vec![
+///     Point3::from_array([0.0, 0.0, 0.0]),
+///     Point3::from_array([1.0, 0.0, 0.0]),
+///     Point3::from_array([0.0, 1.0, 0.0]),
+/// ];
+/// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 0.0, 1.0]));
+///
+/// let bvh = Bvh::new(&mesh);
+/// let ray = Ray {
+///     origin: Point3::from_array([0.25, 0.25, 2.0]),
+///     direction: Point3::from_array([0.0, 0.0, -1.0]),
+/// };
+/// let hit = bvh.cast_ray(&ray).unwrap();
+/// assert_eq!(hit.triangle, 0);
+/// assert_eq!(hit.distance, 2.0);
+/// ```
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Corner points, ordered so that every leaf covers a contiguous range
+    triangles: Vec<[Point3; 3]>,
+    /// Index in the mesh of each entry in `triangles`
+    triangle_indices: Vec<u32>,
+}
+
+impl Bvh {
+    /// Splits the triangles at the median of the longest axis, so the tree is always balanced
+    pub fn new(mesh: &Mesh) -> Self {
+        let mut build_triangles: Vec<BuildTriangle> = (0..mesh.triangle_count())
+            .map(|triangle| {
+                let bounds =
+                    Bounds::from_triangle(&mesh.triangle_points(triangle).map(Point3::clone));
+                BuildTriangle {
+                    index: triangle as u32,
+                    bounds,
+                    center: bounds.center(),
+                }
+            })
+            .collect();
+        let mut nodes = Vec::new();
+        if !build_triangles.is_empty() {
+            build_node(&mut nodes, &mut build_triangles, 0);
+        }
+        let triangle_indices: Vec<u32> = build_triangles
+            .iter()
+            .map(|triangle| triangle.index)
+            .collect();
+        let triangles = triangle_indices
+            .iter()
+            .map(|&triangle| mesh.triangle_points(triangle as usize).map(Point3::clone))
+            .collect();
+        Bvh {
+            nodes,
+            triangles,
+            triangle_indices,
+        }
+    }
+
+    /// The first triangle along the ray. Triangles are hit from both sides.
+    pub fn cast_ray(&self, ray: &Ray) -> Option<RayHit> {
+        let origin = ray.origin.to_array();
+        let inverse_direction = ray.direction.to_array().map(|component| 1.0 / component);
+        let mut closest: Option<(usize, f32)> = None;
+        let mut stack = self.root();
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let max_distance = closest.map_or(f32::INFINITY, |(_, distance)| distance);
+            if node
+                .bounds
+                .ray_distance(&origin, &inverse_direction, max_distance)
+                .is_none()
+            {
+                continue;
+            }
+            if node.count > 0 {
+                for position in node_range(node) {
+                    let Some(distance) = intersect_triangle(ray, &self.triangles[position]) else {
+                        continue;
+                    };
+                    if closest.is_none_or(|(_, closest_distance)| distance < closest_distance) {
+                        closest = Some((position, distance));
+                    }
+                }
+                continue;
+            }
+            // the nearer child is popped first, so that far away nodes can be skipped more often
+            let children = [node_index + 1, node.start as usize];
+            let [first, second] = children.map(|child| {
+                self.nodes[child]
+                    .bounds
+                    .ray_distance(&origin, &inverse_direction, max_distance)
+                    .unwrap_or(f32::INFINITY)
+            });
+            let (near_child, far_child) = if second < first {
+                (children[1], children[0])
+            } else {
+                (children[0], children[1])
+            };
+            stack.push(far_child);
+            stack.push(near_child);
+        }
+        closest.map(|(position, distance)| RayHit {
+            triangle: self.triangle_indices[position] as usize,
+            distance,
+            point: ray.point_at(distance),
+        })
+    }
+
+    /// The closest point on any triangle, `None` for a mesh without triangles
+    pub fn nearest_point(&self, point: &Point3) -> Option<NearestPoint> {
+        let query = point.to_array();
+        let mut closest: Option<(usize, Point3, f32)> = None;
+        let mut stack = self.root();
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            let max_distance_squared = closest
+                .as_ref()
+                .map_or(f32::INFINITY, |(_, _, distance)| *distance);
+            if node.bounds.distance_squared(&query) > max_distance_squared {
+                continue;
+            }
+            if node.count > 0 {
+                for position in node_range(node) {
+                    let candidate = closest_point_on_triangle(point, &self.triangles[position]);
+                    let offset = candidate.relative_to(point);
+                    let distance_squared = dot_product(&offset, &offset);
+                    if closest
+                        .as_ref()
+                        .is_none_or(|(_, _, distance)| distance_squared < *distance)
+                    {
+                        closest = Some((position, candidate, distance_squared));
+                    }
+                }
+                continue;
+            }
+            let children = [node_index + 1, node.start as usize];
+            let [first, second] =
+                children.map(|child| self.nodes[child].bounds.distance_squared(&query));
+            let (near_child, far_child) = if second < first {
+                (children[1], children[0])
+            } else {
+                (children[0], children[1])
+            };
+            stack.push(far_child);
+            stack.push(near_child);
+        }
+        closest.map(|(position, point, distance_squared)| NearestPoint {
+            triangle: self.triangle_indices[position] as usize,
+            point,
+            distance: distance_squared.sqrt(),
+        })
+    }
+
+    /// Indices of the triangles whose bounding boxes overlap the box, in ascending order
+    pub fn triangles_in_box(&self, bounding_box: &BoundingBox) -> Vec<usize> {
+        let [min, max] = bounding_box.get_min_max();
+        let query = Bounds {
+            min: min.to_array(),
+            max: max.to_array(),
+        };
+        self.collect_triangles(
+            |bounds| bounds.overlap_with_box(&query),
+            |triangle| Bounds::from_triangle(triangle).overlap_with_box(&query) != Overlap::Outside,
+        )
+    }
+
+    /// Indices of the triangles that are not completely behind any of the planes, in ascending order.
+    /// The planes face inwards, like the sides of a view frustum.
+    pub fn triangles_in_frustum(&self, planes: &[Plane]) -> Vec<usize> {
+        self.collect_triangles(
+            |bounds| bounds.overlap_with_planes(planes),
+            |triangle| {
+                planes.iter().all(|plane| {
+                    triangle
+                        .iter()
+                        .any(|point| plane.signed_distance(point) >= 0.0)
+                })
+            },
+        )
+    }
+
+    /// Tests triangles only in nodes that partially overlap the query volume
+    fn collect_triangles(
+        &self,
+        node_overlap: impl Fn(&Bounds) -> Overlap,
+        triangle_overlaps: impl Fn(&[Point3; 3]) -> bool,
+    ) -> Vec<usize> {
+        let mut triangles = Vec::new();
+        let mut stack = self.root();
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            match node_overlap(&node.bounds) {
+                Overlap::Outside => (),
+                Overlap::Inside => triangles.extend(
+                    self.subtree_range(node_index)
+                        .map(|position| self.triangle_indices[position] as usize),
+                ),
+                Overlap::Partial if node.count > 0 => triangles.extend(
+                    node_range(node)
+                        .filter(|&position| triangle_overlaps(&self.triangles[position]))
+                        .map(|position| self.triangle_indices[position] as usize),
+                ),
+                Overlap::Partial => stack.extend([node.start as usize, node_index + 1]),
+            }
+        }
+        triangles.sort_unstable();
+        triangles
+    }
+
+    fn root(&self) -> Vec<usize> {
+        if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        }
+    }
+
+    /// The triangles of a subtree are contiguous, from its leftmost to its rightmost leaf
+    fn subtree_range(&self, node_index: usize) -> std::ops::Range<usize> {
+        let mut first = node_index;
+        while self.nodes[first].count == 0 {
+            first += 1;
+        }
+        let mut last = node_index;
+        while self.nodes[last].count == 0 {
+            last = self.nodes[last].start as usize;
+        }
+        self.nodes[first].start as usize..node_range(&self.nodes[last]).end
+    }
+}
+
+fn node_range(node: &Node) -> std::ops::Range<usize> {
+    node.start as usize..(node.start + node.count) as usize
+}
+
+/// Appends the node for `triangles` and its children in depth first order
+fn build_node(nodes: &mut Vec<Node>, triangles: &mut [BuildTriangle], start: usize) {
+    let bounds = triangles.iter().fold(Bounds::empty(), |bounds, triangle| {
+        bounds.union(&triangle.bounds)
+    });
+    let node_index = nodes.len();
+    nodes.push(Node {
+        bounds,
+        start: start as u32,
+        count: triangles.len() as u32,
+    });
+    if triangles.len() <= MAX_LEAF_SIZE {
+        return;
+    }
+    let axis = triangles
+        .iter()
+        .fold(Bounds::empty(), |bounds, triangle| {
+            bounds.expanded(&triangle.center)
+        })
+        .longest_axis();
+    let middle = triangles.len() / 2;
+    triangles.select_nth_unstable_by(middle, |a, b| a.center[axis].total_cmp(&b.center[axis]));
+    let (first_half, second_half) = triangles.split_at_mut(middle);
+    build_node(nodes, first_half, start);
+    nodes[node_index].start = nodes.len() as u32;
+    nodes[node_index].count = 0;
+    build_node(nodes, second_half, start + middle);
+}
+
+/// Möller–Trumbore intersection, returns the position along the ray
+fn intersect_triangle(ray: &Ray, [a, b, c]: &[Point3; 3]) -> Option<f32> {
+    let edge1 = b.relative_to(a);
+    let edge2 = c.relative_to(a);
+    let p = cross_product(ray.direction.clone(), edge2.clone());
+    let determinant = dot_product(&edge1, &p);
+    if determinant == 0.0 {
+        // the ray is parallel to the triangle
+        return None;
+    }
+    let inverse_determinant = 1.0 / determinant;
+    let to_origin = ray.origin.relative_to(a);
+    let u = dot_product(&to_origin, &p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross_product(to_origin, edge1);
+    let v = dot_product(&ray.direction, &q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let distance = dot_product(&edge2, &q) * inverse_determinant;
+    (distance >= 0.0).then_some(distance)
+}
+
+/// From Real-Time Collision Detection by Christer Ericson, section 5.1.5
+fn closest_point_on_triangle(point: &Point3, [a, b, c]: &[Point3; 3]) -> Point3 {
+    let along = |start: &Point3, direction: &Point3, factor: f32| {
+        start.add(&direction.map(|component| component * factor))
+    };
+    let ab = b.relative_to(a);
+    let ac = c.relative_to(a);
+    let ap = point.relative_to(a);
+    let d1 = dot_product(&ab, &ap);
+    let d2 = dot_product(&ac, &ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a.clone();
+    }
+    let bp = point.relative_to(b);
+    let d3 = dot_product(&ab, &bp);
+    let d4 = dot_product(&ac, &bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b.clone();
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return along(a, &ab, d1 / (d1 - d3));
+    }
+    let cp = point.relative_to(c);
+    let d5 = dot_product(&ab, &cp);
+    let d6 = dot_product(&ac, &cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c.clone();
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return along(a, &ac, d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return along(b, &c.relative_to(b), (d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denominator = 1.0 / (va + vb + vc);
+    along(&along(a, &ab, vb * denominator), &ac, vc * denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bvh, Ray};
+    use crate::general::positions_3d::{BoundingBox, Mesh, Point as Point3};
+    use crate::mesh_processing::section::Plane;
+    use crate::mesh_processing::test_meshes::grid;
+
+    #[test]
+    fn test_ray_hits_the_closest_triangle() {
+        let mut mesh = grid(10);
+        // a second grid in front of the first one
+        let point_count = mesh.points.len();
+        for point in mesh.points.clone() {
+            mesh.points
+                .push(point.add(&Point3::from_array([0.0, 0.0, 1.0])));
+        }
+        for triangle in 0..mesh.triangle_count() {
+            let indices = mesh
+                .triangle_indices(triangle)
+                .map(|index| index + point_count);
+            mesh.push_triangle(indices, Point3::from_array([0.0, 0.0, 1.0]));
+        }
+
+        let bvh = Bvh::new(&mesh);
+        let ray = Ray {
+            origin: Point3::from_array([3.7, 5.2, 5.0]),
+            direction: Point3::from_array([0.0, 0.0, -2.0]),
+        };
+        let hit = bvh.cast_ray(&ray).unwrap();
+        assert_eq!(hit.distance, 2.0);
+        assert_eq!(hit.point, Point3::from_array([3.7, 5.2, 1.0]));
+        assert!(mesh.triangle_indices(hit.triangle)[0] >= point_count);
+
+        let miss = Ray {
+            origin: Point3::from_array([12.0, 5.0, 5.0]),
+            direction: Point3::from_array([0.0, 0.0, -1.0]),
+        };
+        assert_eq!(bvh.cast_ray(&miss), None);
+        let away = Ray {
+            origin: Point3::from_array([3.0, 5.0, 5.0]),
+            direction: Point3::from_array([0.0, 0.0, 1.0]),
+        };
+        assert_eq!(bvh.cast_ray(&away), None);
+    }
+
+    #[test]
+    fn test_nearest_point() {
+        let bvh = Bvh::new(&grid(10));
+        let above = bvh
+            .nearest_point(&Point3::from_array([2.5, 7.25, 3.0]))
+            .unwrap();
+        assert_eq!(above.point, Point3::from_array([2.5, 7.25, 0.0]));
+        assert_eq!(above.distance, 3.0);
+
+        let beside = bvh
+            .nearest_point(&Point3::from_array([14.0, 13.0, 0.0]))
+            .unwrap();
+        assert_eq!(beside.point, Point3::from_array([10.0, 10.0, 0.0]));
+        assert_eq!(beside.distance, 5.0);
+
+        assert_eq!(Bvh::new(&Mesh::new()).nearest_point(&Point3::new()), None);
+    }
+
+    #[test]
+    fn test_box_and_frustum_queries_match_brute_force() {
+        let mesh = grid(20);
+        let bvh = Bvh::new(&mesh);
+        let bounding_box = BoundingBox::new(&vec![
+            Point3::from_array([4.5, 2.5, -1.0]),
+            Point3::from_array([9.5, 11.5, 1.0]),
+        ]);
+        let expected: Vec<usize> = (0..mesh.triangle_count())
+            .filter(|&triangle| {
+                let points = mesh.triangle_points(triangle);
+                let [min, max] =
+                    BoundingBox::new(&points.map(Point3::clone).to_vec()).get_min_max();
+                min.x <= 9.5 && max.x >= 4.5 && min.y <= 11.5 && max.y >= 2.5
+            })
+            .collect();
+        assert_eq!(bvh.triangles_in_box(&bounding_box), expected);
+
+        // a wedge that contains everything with x >= y
+        let planes = [Plane {
+            point: Point3::new(),
+            normal: Point3::from_array([1.0, -1.0, 0.0]).normalized(),
+        }];
+        let expected: Vec<usize> = (0..mesh.triangle_count())
+            .filter(|&triangle| {
+                mesh.triangle_points(triangle)
+                    .iter()
+                    .any(|point| point.x >= point.y)
+            })
+            .collect();
+        assert_eq!(bvh.triangles_in_frustum(&planes), expected);
+        assert_eq!(bvh.triangles_in_frustum(&[]).len(), mesh.triangle_count());
+    }
+}
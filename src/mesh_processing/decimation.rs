@@ -0,0 +1,521 @@
+use crate::general::positions_3d::{
+    cross_product, distance, dot_product, to_mesh_index, Mesh, Point as Point3,
+    Triangle as Triangle3,
+};
+use crate::mesh_processing::half_edge::HalfEdgeMesh;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
+
+/// How much boundary edges resist being collapsed, compared to edges inside the surface.
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// How far a mesh should be decimated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DecimationTarget {
+    TriangleCount(usize),
+    /// Fraction of the current triangle count to keep, between 0 and 1.
+    Ratio(f32),
+}
+
+impl FromStr for DecimationTarget {
+    type Err = String;
+
+    /// Parses whole numbers as a triangle count and decimal numbers as a ratio,
+    /// for example "5000" or "0.25".
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        if let Ok(triangle_count) = string.parse() {
+            return Ok(DecimationTarget::TriangleCount(triangle_count));
+        }
+        match string.parse::<f32>() {
+            Ok(ratio) if ratio > 0.0 && ratio <= 1.0 => Ok(DecimationTarget::Ratio(ratio)),
+            _ => Err(format!(
+                "'{string}' is neither a triangle count nor a ratio between 0 and 1"
+            )),
+        }
+    }
+}
+
+impl DecimationTarget {
+    fn triangle_count(&self, current_triangle_count: usize) -> usize {
+        match self {
+            DecimationTarget::TriangleCount(count) => *count,
+            DecimationTarget::Ratio(ratio) => {
+                (current_triangle_count as f32 * ratio).round() as usize
+            }
+        }
+    }
+}
+
+impl Mesh {
+    /// Reduces the amount of triangles by collapsing edges,
+    /// always choosing the collapse that changes the shape of the surface the least
+    /// according to the quadric error metric (Garland and Heckbert, 1997).
+    ///
+    /// The remaining triangles keep their groups, materials, texture coordinates and polygons.
+    /// Vertex normals are removed, since they do not fit the new surface.
+    pub fn decimate(&mut self, target: DecimationTarget) {
+        let target_triangle_count = target.triangle_count(self.triangle_count());
+        if target_triangle_count >= self.triangle_count() {
+            return;
+        }
+
+        let mut decimator = Decimator::new(self);
+        decimator.collapse_until(target_triangle_count);
+        decimator.write_to_mesh(self);
+    }
+}
+
+/// Symmetric 4x4 matrix that measures the squared distance to a set of planes.
+/// Only the upper triangle is stored: a², ab, ac, ad, b², bc, bd, c², cd, d².
+#[derive(Clone, Copy, Debug)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn zero() -> Self {
+        Quadric([0.0; 10])
+    }
+
+    /// Plane `ax + by + cz + d = 0`, where (a, b, c) is a unit vector
+    fn from_plane(normal: &Point3, point_on_plane: &Point3, weight: f64) -> Self {
+        let [a, b, c] = normal.to_array().map(|component| component as f64);
+        let [x, y, z] = point_on_plane.to_array().map(|component| component as f64);
+        let d = -(a * x + b * y + c * z);
+        Quadric(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|q| q * weight),
+        )
+    }
+
+    fn add(&self, other: &Quadric) -> Quadric {
+        let mut sum = self.0;
+        for (value, other_value) in sum.iter_mut().zip(other.0) {
+            *value += other_value;
+        }
+        Quadric(sum)
+    }
+
+    fn error(&self, point: &Point3) -> f64 {
+        let q = &self.0;
+        let [x, y, z] = point.to_array().map(|component| component as f64);
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// Returns the point with the smallest error, if there is exactly one such point.
+    /// Solves the linear system with Cramer's rule.
+    fn optimal_point(&self) -> Option<Point3> {
+        let q = &self.0;
+        let m = [[q[0], q[1], q[2]], [q[1], q[4], q[5]], [q[2], q[5], q[7]]];
+        let rhs = [-q[3], -q[6], -q[8]];
+        let determinant = determinant3(&m);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+
+        let mut solution = [0.0; 3];
+        for (column, value) in solution.iter_mut().enumerate() {
+            let mut replaced = m;
+            for row in 0..3 {
+                replaced[row][column] = rhs[row];
+            }
+            *value = (determinant3(&replaced) / determinant) as f32;
+        }
+        Some(Point3::from_array(solution))
+    }
+}
+
+fn determinant3(m: &[[f64; 3]; 3]) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// A possible edge collapse, only valid while both vertices have the same versions as when it was created.
+struct Collapse {
+    cost: f64,
+    kept_vertex: usize,
+    removed_vertex: usize,
+    position: Point3,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // reversed, so that the BinaryHeap returns the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Decimator {
+    points: Vec<Point3>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    vertex_is_alive: Vec<bool>,
+    faces: Vec<[usize; 3]>,
+    face_is_alive: Vec<bool>,
+    faces_per_vertex: Vec<Vec<usize>>,
+    alive_face_count: usize,
+    collapses: BinaryHeap<Collapse>,
+}
+
+impl Decimator {
+    fn new(mesh: &Mesh) -> Self {
+        let points = mesh.points.clone();
+        let faces: Vec<[usize; 3]> = (0..mesh.triangle_count())
+            .map(|triangle| mesh.triangle_indices(triangle))
+            .collect();
+        let mut quadrics = vec![Quadric::zero(); points.len()];
+        let mut faces_per_vertex = vec![Vec::new(); points.len()];
+
+        for (face_index, face) in faces.iter().enumerate() {
+            let face_points = [&points[face[0]], &points[face[1]], &points[face[2]]];
+            let area = triangle_area(&face_points);
+            let normal = Triangle3::get_normal(&face_points);
+            for &vertex in face {
+                faces_per_vertex[vertex].push(face_index);
+                if area > 0.0 {
+                    let plane = Quadric::from_plane(&normal, face_points[0], area as f64);
+                    quadrics[vertex] = quadrics[vertex].add(&plane);
+                }
+            }
+        }
+
+        let half_edges = HalfEdgeMesh::new(mesh);
+        // boundary edges get a plane perpendicular to their face, so that the outline is kept
+        for edge in half_edges.edges().iter().filter(|edge| edge.is_boundary()) {
+            let (a, b) = &edge.vertices;
+            let face = faces[half_edges.triangle(edge.half_edges[0])];
+            let face_normal =
+                Triangle3::get_normal(&[&points[face[0]], &points[face[1]], &points[face[2]]]);
+            let edge_direction = points[*b].relative_to(&points[*a]);
+            let plane_normal = cross_product(edge_direction, face_normal).normalized();
+            if !plane_normal.x.is_finite() {
+                continue;
+            }
+            let weight = BOUNDARY_WEIGHT * (distance(&points[*a], &points[*b]) as f64).powi(2);
+            let plane = Quadric::from_plane(&plane_normal, &points[*a], weight);
+            quadrics[*a] = quadrics[*a].add(&plane);
+            quadrics[*b] = quadrics[*b].add(&plane);
+        }
+
+        let mut decimator = Decimator {
+            versions: vec![0; points.len()],
+            vertex_is_alive: vec![true; points.len()],
+            face_is_alive: vec![true; faces.len()],
+            alive_face_count: faces.len(),
+            points,
+            quadrics,
+            faces,
+            faces_per_vertex,
+            collapses: BinaryHeap::new(),
+        };
+        for edge in half_edges.edges() {
+            let (a, b) = edge.vertices;
+            decimator.add_collapse(a, b);
+        }
+        decimator
+    }
+
+    fn add_collapse(&mut self, a: usize, b: usize) {
+        let quadric = self.quadrics[a].add(&self.quadrics[b]);
+        let midpoint = self.points[a].add(&self.points[b]).map(|c| c / 2.0);
+        let candidates = [
+            quadric.optimal_point(),
+            Some(self.points[a].clone()),
+            Some(self.points[b].clone()),
+            Some(midpoint),
+        ];
+        let (position, cost) = candidates
+            .into_iter()
+            .flatten()
+            .map(|position| {
+                let cost = quadric.error(&position);
+                (position, cost)
+            })
+            .min_by(|(_, cost_1), (_, cost_2)| cost_1.total_cmp(cost_2))
+            .expect("at least the endpoints are candidates");
+
+        self.collapses.push(Collapse {
+            cost,
+            kept_vertex: a,
+            removed_vertex: b,
+            position,
+            versions: (self.versions[a], self.versions[b]),
+        });
+    }
+
+    fn collapse_until(&mut self, target_face_count: usize) {
+        while self.alive_face_count > target_face_count {
+            let Some(collapse) = self.collapses.pop() else {
+                break;
+            };
+            let (a, b) = (collapse.kept_vertex, collapse.removed_vertex);
+            let is_outdated = !self.vertex_is_alive[a]
+                || !self.vertex_is_alive[b]
+                || collapse.versions != (self.versions[a], self.versions[b]);
+            if is_outdated
+                || !self.collapse_keeps_manifold(a, b)
+                || self.collapse_flips_faces(a, b, &collapse.position)
+            {
+                continue;
+            }
+            self.collapse_edge(a, b, collapse.position);
+        }
+    }
+
+    /// The link condition: the only vertices connected to both `a` and `b` may be the opposite corners
+    /// of the faces on the edge between them, and no face of `b` may become a copy of a face of `a`.
+    /// Otherwise the collapse would create edges with more than two faces, or pinch the surface.
+    fn collapse_keeps_manifold(&self, a: usize, b: usize) -> bool {
+        let alive_faces = |vertex: usize| {
+            self.faces_per_vertex[vertex]
+                .iter()
+                .filter(|face_index| self.face_is_alive[**face_index])
+                .map(|face_index| self.faces[*face_index])
+        };
+        let neighbours = |vertex: usize| {
+            let mut neighbours: Vec<usize> = alive_faces(vertex)
+                .flatten()
+                .filter(|neighbour| *neighbour != vertex)
+                .collect();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            neighbours
+        };
+        let edge_opposite_corners: Vec<usize> = alive_faces(a)
+            .filter(|face| face.contains(&b))
+            .flat_map(|face| {
+                face.into_iter()
+                    .filter(|vertex| *vertex != a && *vertex != b)
+            })
+            .collect();
+        let neighbours_of_b = neighbours(b);
+        let shares_other_neighbours = neighbours(a).iter().any(|neighbour| {
+            *neighbour != b
+                && neighbours_of_b.contains(neighbour)
+                && !edge_opposite_corners.contains(neighbour)
+        });
+        if shares_other_neighbours {
+            return false;
+        }
+
+        let sorted = |mut face: [usize; 3]| {
+            face.sort_unstable();
+            face
+        };
+        let faces_of_a: Vec<[usize; 3]> = alive_faces(a).map(sorted).collect();
+        !alive_faces(b)
+            .filter(|face| !face.contains(&a))
+            .map(|face| sorted(face.map(|vertex| if vertex == b { a } else { vertex })))
+            .any(|face| faces_of_a.contains(&face))
+    }
+
+    /// Checks if moving both vertices to `position` would turn any remaining face upside down
+    fn collapse_flips_faces(&self, a: usize, b: usize, position: &Point3) -> bool {
+        for &vertex in &[a, b] {
+            for &face_index in &self.faces_per_vertex[vertex] {
+                let face = self.faces[face_index];
+                if !self.face_is_alive[face_index] || (face.contains(&a) && face.contains(&b)) {
+                    continue;
+                }
+                let old_points = face.map(|v| &self.points[v]);
+                let new_points = face.map(|v| {
+                    if v == vertex {
+                        position
+                    } else {
+                        &self.points[v]
+                    }
+                });
+                let old_normal = Triangle3::get_normal(&old_points);
+                let new_normal = Triangle3::get_normal(&new_points);
+                if !new_normal.x.is_finite() || dot_product(&old_normal, &new_normal) < 0.0 {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn collapse_edge(&mut self, a: usize, b: usize, position: Point3) {
+        self.points[a] = position;
+        self.quadrics[a] = self.quadrics[a].add(&self.quadrics[b]);
+        self.vertex_is_alive[b] = false;
+        self.versions[a] += 1;
+
+        for face_index in std::mem::take(&mut self.faces_per_vertex[b]) {
+            if !self.face_is_alive[face_index] {
+                continue;
+            }
+            let face = &mut self.faces[face_index];
+            if face.contains(&a) {
+                self.face_is_alive[face_index] = false;
+                self.alive_face_count -= 1;
+            } else {
+                for vertex in face.iter_mut() {
+                    if *vertex == b {
+                        *vertex = a;
+                    }
+                }
+                self.faces_per_vertex[a].push(face_index);
+            }
+        }
+
+        let face_is_alive = &self.face_is_alive;
+        self.faces_per_vertex[a].retain(|face_index| face_is_alive[*face_index]);
+
+        let mut neighbours: Vec<usize> = self.faces_per_vertex[a]
+            .iter()
+            .flat_map(|face_index| self.faces[*face_index])
+            .filter(|vertex| *vertex != a)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for neighbour in neighbours {
+            self.add_collapse(a, neighbour);
+        }
+    }
+
+    fn write_to_mesh(self, mesh: &mut Mesh) {
+        let mut new_indices = vec![usize::MAX; self.points.len()];
+        let mut points = Vec::new();
+        for (index, point) in self.points.into_iter().enumerate() {
+            if self.vertex_is_alive[index] {
+                new_indices[index] = points.len();
+                points.push(point);
+            }
+        }
+
+        // the faces keep their order, so their face data stays valid for the remaining faces
+        for (triangle, face) in self.faces.iter().enumerate() {
+            if !self.face_is_alive[triangle] {
+                continue;
+            }
+            let indices = face.map(|vertex| new_indices[vertex]);
+            mesh.triangles[triangle] = indices.map(to_mesh_index);
+            mesh.face_normals[triangle] = Triangle3::get_normal(&indices.map(|i| &points[i]));
+        }
+        mesh.points = points;
+        mesh.retain_triangles(&self.face_is_alive);
+        mesh.face_data.normal_indices = None;
+        mesh.normals.clear();
+    }
+}
+
+fn triangle_area(points: &[&Point3; 3]) -> f32 {
+    let cross = cross_product(
+        points[1].relative_to(points[0]),
+        points[2].relative_to(points[0]),
+    );
+    dot_product(&cross, &cross).sqrt() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DecimationTarget;
+    use crate::mesh_processing::statistics::MeshStatistics;
+    use crate::mesh_processing::test_meshes::{grid, tetrahedron};
+
+    #[test]
+    fn test_parse_decimation_target() {
+        assert_eq!("500".parse(), Ok(DecimationTarget::TriangleCount(500)));
+        assert_eq!("0.5".parse(), Ok(DecimationTarget::Ratio(0.5)));
+        assert!("1.5".parse::<DecimationTarget>().is_err());
+        assert!("abc".parse::<DecimationTarget>().is_err());
+    }
+
+    #[test]
+    fn test_decimating_flat_grid_keeps_it_flat_and_facing_up() {
+        let mut mesh = grid(8);
+        mesh.decimate(DecimationTarget::Ratio(0.25));
+
+        assert!(mesh.triangle_count() <= 32);
+        assert!(mesh.points.iter().all(|point| point.z.abs() < 1e-4));
+        assert!(mesh.face_normals.iter().all(|normal| normal.z > 0.99));
+    }
+
+    #[test]
+    fn test_decimating_flat_grid_keeps_its_outline() {
+        let mut mesh = grid(6);
+        mesh.decimate(DecimationTarget::TriangleCount(10));
+
+        for corner in [[0.0, 0.0], [6.0, 0.0], [0.0, 6.0], [6.0, 6.0]] {
+            assert!(mesh
+                .points
+                .iter()
+                .any(|point| (point.x - corner[0]).abs() < 1e-3
+                    && (point.y - corner[1]).abs() < 1e-3));
+        }
+    }
+
+    #[test]
+    fn test_decimating_keeps_face_data() {
+        let mut mesh = grid(8);
+        mesh.groups = vec![String::from("Grid")];
+        for triangle in 0..mesh.triangle_count() {
+            mesh.set_group(triangle, 0);
+            // the left and right half have different materials
+            let is_left = mesh
+                .triangle_points(triangle)
+                .iter()
+                .all(|point| point.x <= 4.0);
+            mesh.set_material(triangle, if is_left { 0 } else { 1 });
+            mesh.set_uv_indices(triangle, [triangle; 3]);
+        }
+        mesh.decimate(DecimationTarget::Ratio(0.25));
+
+        let count = mesh.triangle_count();
+        assert_eq!(mesh.face_data.groups, Some(vec![0; count]));
+        let materials = mesh.face_data.materials.as_ref().unwrap();
+        assert_eq!(materials.len(), count);
+        assert!(materials.contains(&0) && materials.contains(&1));
+        assert_eq!(mesh.face_data.uv_indices.as_ref().unwrap().len(), count);
+        assert!(mesh.face_data.normal_indices.is_none());
+    }
+
+    #[test]
+    fn test_collapses_do_not_create_non_manifold_edges() {
+        // every collapse of a tetrahedron would leave two triangles on the same three vertices
+        let mut mesh = tetrahedron();
+        mesh.decimate(DecimationTarget::TriangleCount(0));
+        assert_eq!(mesh.triangle_count(), 4);
+        assert!(MeshStatistics::new(&mesh).is_watertight);
+
+        let mut mesh = grid(6);
+        mesh.decimate(DecimationTarget::TriangleCount(2));
+        assert_eq!(MeshStatistics::new(&mesh).non_manifold_vertex_count, 0);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::general::positions_3d::Mesh;
+use crate::mesh_processing::bvh::Bvh;
+use crate::mesh_processing::statistics::{fields_to_json, number_to_json};
+
+/// Brightness of points without deviation, so that they are still visible
+const MIN_BRIGHTNESS: f32 = 0.1;
+
+/// How far the surface of one mesh is from the surface of another mesh,
+/// for example a scan compared to the CAD model it was made from.
+///
+/// Distances are measured from the vertices of one mesh to the closest point on the triangles of the other one.
+/// The meshes are compared in global coordinates, so they may have a different `Mesh::origin`.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+/// use rust_obj_terminal_viewer::mesh_processing::deviation::Deviation;
+///
+/// let mut mesh = Mesh::new();
+/// mesh.points = vec![
+///     Point3::from_array([0.0, 0.0, 0.0]),
+///     Point3::from_array([1.0, 0.0, 0.0]),
+///     Point3::from_array([0.0, 1.0, 0.0]),
+/// ];
+/// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 0.0, 1.0]));
+/// let mut moved_mesh = mesh.clone();
+/// moved_mesh.translate(&Point3::from_array([0.0, 0.0, 0.5]));
+///
+/// let deviation = Deviation::new(&moved_mesh, &mesh).unwrap();
+/// assert_eq!(deviation.distances, vec![0.5, 0.5, 0.5]);
+/// assert_eq!(deviation.hausdorff_distance, 0.5);
+/// ```
+#[derive(Debug)]
+pub struct Deviation {
+    /// Distance from every point of the first mesh to the second mesh
+    pub distances: Vec<f32>,
+    /// Largest distance from the first mesh to the second mesh
+    pub max_distance: f32,
+    pub mean_distance: f32,
+    pub rms_distance: f32,
+    /// Largest distance in either direction, so that parts that are missing in the first mesh are found as well
+    pub hausdorff_distance: f32,
+}
+
+impl Deviation {
+    /// Returns an error if one of the meshes has no triangles, since there is no surface to measure to
+    pub fn new(mesh: &Mesh, other: &Mesh) -> Result<Self, String> {
+        let distances =
+            distances_to_surface(mesh, other).ok_or("the second mesh has no triangles")?;
+        let reverse_distances =
+            distances_to_surface(other, mesh).ok_or("the first mesh has no triangles")?;
+
+        let max = |distances: &[f32]| distances.iter().copied().fold(0.0, f32::max);
+        let count = distances.len().max(1) as f32;
+        let mean_distance = distances.iter().sum::<f32>() / count;
+        let rms_distance = (distances
+            .iter()
+            .map(|distance| distance * distance)
+            .sum::<f32>()
+            / count)
+            .sqrt();
+        Ok(Deviation {
+            max_distance: max(&distances),
+            mean_distance,
+            rms_distance,
+            hausdorff_distance: max(&distances).max(max(&reverse_distances)),
+            distances,
+        })
+    }
+
+    /// Brightness from dark to bright for every point of the first mesh,
+    /// the brightest points are at least `scale` away from the second mesh
+    pub fn brightness(&self, scale: f32) -> Vec<f32> {
+        self.distances
+            .iter()
+            .map(|distance| {
+                let fraction = if scale > 0.0 {
+                    (distance / scale).min(1.0)
+                } else {
+                    0.0
+                };
+                MIN_BRIGHTNESS + fraction * (1.0 - MIN_BRIGHTNESS)
+            })
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        [
+            format!("max distance: {}", self.max_distance),
+            format!("mean distance: {}", self.mean_distance),
+            format!("rms distance: {}", self.rms_distance),
+            format!("hausdorff distance: {}", self.hausdorff_distance),
+        ]
+        .join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        fields_to_json(&[
+            ("max_distance", number_to_json(self.max_distance)),
+            ("mean_distance", number_to_json(self.mean_distance)),
+            ("rms_distance", number_to_json(self.rms_distance)),
+            (
+                "hausdorff_distance",
+                number_to_json(self.hausdorff_distance),
+            ),
+        ])
+    }
+}
+
+/// `None` if `surface` has no triangles
+fn distances_to_surface(mesh: &Mesh, surface: &Mesh) -> Option<Vec<f32>> {
+    let bvh = Bvh::new(surface);
+    // moves the points into the local coordinates of the surface
+    let offset = surface.origin.local_offset_to(&mesh.origin);
+    mesh.points
+        .iter()
+        .map(|point| {
+            bvh.nearest_point(&point.add(&offset))
+                .map(|nearest| nearest.distance)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deviation;
+    use crate::general::positions_3d::{GlobalPoint, Mesh, Point as Point3};
+    use crate::mesh_processing::test_meshes::square;
+
+    #[test]
+    fn test_identical_meshes_have_no_deviation() {
+        let mesh = square();
+        let deviation = Deviation::new(&mesh, &mesh).unwrap();
+        assert_eq!(deviation.distances, vec![0.0; 4]);
+        assert_eq!(deviation.hausdorff_distance, 0.0);
+        assert_eq!(deviation.brightness(1.0), vec![0.1; 4]);
+    }
+
+    #[test]
+    fn test_statistics_of_a_bent_square() {
+        let mut bent_mesh = square();
+        bent_mesh.points[2].z = 4.0;
+        let deviation = Deviation::new(&bent_mesh, &square()).unwrap();
+        assert_eq!(deviation.distances, vec![0.0, 0.0, 4.0, 0.0]);
+        assert_eq!(deviation.max_distance, 4.0);
+        assert_eq!(deviation.mean_distance, 1.0);
+        assert_eq!(deviation.rms_distance, 2.0);
+        assert!((deviation.brightness(8.0)[2] - 0.55).abs() < 1e-6);
+        assert!(deviation.to_json().contains("\"rms_distance\": 2"));
+    }
+
+    #[test]
+    fn test_hausdorff_distance_includes_missing_parts() {
+        let mut half_mesh = square();
+        half_mesh.retain_triangles(&[true, false]);
+        let deviation = Deviation::new(&half_mesh, &square()).unwrap();
+        assert_eq!(deviation.max_distance, 0.0);
+        // the corner that only the full square has is half a diagonal away from the remaining triangle
+        assert!((deviation.hausdorff_distance - 0.5_f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_meshes_are_compared_in_global_coordinates() {
+        let mut rebased_mesh = square();
+        rebased_mesh.origin = GlobalPoint::from_array([500_000.0, 0.0, 0.0]);
+        rebased_mesh.translate(&Point3::from_array([-500_000.0, 0.0, 0.0]));
+        let deviation = Deviation::new(&rebased_mesh, &square()).unwrap();
+        assert_eq!(deviation.hausdorff_distance, 0.0);
+
+        assert!(Deviation::new(&square(), &Mesh::new()).is_err());
+    }
+}
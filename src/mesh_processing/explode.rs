@@ -0,0 +1,118 @@
+use crate::general::positions_3d::{BoundingBox, Mesh, Point as Point3};
+use crate::mesh_processing::half_edge::HalfEdgeMesh;
+
+/// Moves the connected components of a mesh apart, to look inside assemblies that are exported
+/// as a single object without groups.
+///
+/// Every component is moved along the line from the center of the mesh to its own center.
+/// With a factor of 1 its distance to the center of the mesh doubles.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+/// use rust_obj_terminal_viewer::mesh_processing::explode::ExplodedView;
+///
+/// let mut mesh = Mesh::new();
+/// for x in [-2.0, -1.0, 1.0, 2.0] {
+///     mesh.points.push(Point3::from_array([x, 0.0, 0.0]));
+///     mesh.points.push(Point3::from_array([x, 1.0, 0.0]));
+/// }
+/// let normal = Point3::from_array([0.0, 0.0, 1.0]);
+/// mesh.push_triangle([0, 2, 3], normal.clone());
+/// mesh.push_triangle([4, 6, 7], normal);
+///
+/// let exploded_view = ExplodedView::new(&mesh);
+/// assert_eq!(exploded_view.component_count(), 2);
+/// let points = exploded_view.exploded_points(&mesh.points, 1.0);
+/// assert_eq!(points[0], Point3::from_array([-3.5, 0.0, 0.0]));
+/// assert_eq!(points[7], Point3::from_array([3.5, 1.0, 0.0]));
+/// ```
+pub struct ExplodedView {
+    /// Offset of the component center from the mesh center, for every point
+    point_offsets: Vec<Point3>,
+    component_count: usize,
+}
+
+impl ExplodedView {
+    /// Points that are not used by any triangle are not moved
+    pub fn new(mesh: &Mesh) -> Self {
+        let components = HalfEdgeMesh::new(mesh).connected_components();
+        let center = BoundingBox::new(&mesh.points).get_center();
+        let mut point_offsets = vec![Point3::new(); mesh.points.len()];
+        for component in &components {
+            let mut vertices: Vec<usize> = component
+                .iter()
+                .flat_map(|&triangle| mesh.triangle_indices(triangle))
+                .collect();
+            vertices.sort_unstable();
+            vertices.dedup();
+            let component_points = vertices
+                .iter()
+                .map(|&vertex| mesh.points[vertex].clone())
+                .collect();
+            let offset = BoundingBox::new(&component_points)
+                .get_center()
+                .relative_to(&center);
+            for vertex in vertices {
+                point_offsets[vertex] = offset.clone();
+            }
+        }
+        ExplodedView {
+            point_offsets,
+            component_count: components.len(),
+        }
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.component_count
+    }
+
+    /// `points` are the points of the mesh the view was made for, 0 returns them unchanged
+    pub fn exploded_points(&self, points: &[Point3], factor: f32) -> Vec<Point3> {
+        points
+            .iter()
+            .zip(&self.point_offsets)
+            .map(|(point, offset)| point.add(&offset.map(|component| component * factor)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExplodedView;
+    use crate::general::positions_3d::{Mesh, Point as Point3};
+
+    /// Two unit squares next to each other along X, and a single unused point
+    fn two_squares() -> Mesh {
+        let mut mesh = Mesh::new();
+        for x in [0.0, 1.0, 3.0, 4.0] {
+            mesh.points.push(Point3::from_array([x, 0.0, 0.0]));
+            mesh.points.push(Point3::from_array([x, 1.0, 0.0]));
+        }
+        mesh.points.push(Point3::from_array([10.0, 0.0, 0.0]));
+        let normal = Point3::from_array([0.0, 0.0, 1.0]);
+        for start in [0, 4] {
+            mesh.push_triangle([start, start + 2, start + 3], normal.clone());
+            mesh.push_triangle([start, start + 3, start + 1], normal.clone());
+        }
+        mesh
+    }
+
+    #[test]
+    fn test_components_move_away_from_the_center() {
+        let mesh = two_squares();
+        let exploded_view = ExplodedView::new(&mesh);
+        assert_eq!(exploded_view.component_count(), 2);
+
+        assert_eq!(
+            exploded_view.exploded_points(&mesh.points, 0.0),
+            mesh.points
+        );
+        // the center of the bounding box is at x = 5, the squares are centered at 0.5 and 3.5
+        let points = exploded_view.exploded_points(&mesh.points, 0.5);
+        assert_eq!(points[0], Point3::from_array([-2.25, 0.0, 0.0]));
+        assert_eq!(points[3], Point3::from_array([-1.25, 1.0, 0.0]));
+        assert_eq!(points[4], Point3::from_array([2.25, 0.0, 0.0]));
+        assert_eq!(points[8], mesh.points[8]);
+    }
+}
@@ -0,0 +1,416 @@
+use crate::general::positions_3d::Mesh;
+use std::collections::{HashMap, VecDeque};
+
+/// An edge between two vertices, with all half-edges that run along it in either direction.
+#[derive(Debug)]
+pub struct Edge {
+    /// The two vertex indices, the smaller one first
+    pub vertices: (usize, usize),
+    pub half_edges: Vec<usize>,
+}
+
+impl Edge {
+    /// Only one triangle uses the edge
+    pub fn is_boundary(&self) -> bool {
+        self.half_edges.len() == 1
+    }
+
+    /// At most two triangles use the edge
+    pub fn is_manifold(&self) -> bool {
+        self.half_edges.len() <= 2
+    }
+}
+
+/// Half-edge adjacency of a mesh, to answer topology questions without scanning all triangles.
+///
+/// Every triangle has three half-edges: half-edge `3 * t + c` starts at corner `c` of triangle `t`
+/// and ends at the next corner. Because of that, a half-edge index also identifies a triangle corner.
+/// Half-edges between the same two vertices belong to the same `Edge`, independent of their direction,
+/// so that meshes with inconsistent winding or non-manifold edges are still represented completely.
+///
+/// The adjacency is not updated when the mesh changes, it has to be built again.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+/// use rust_obj_terminal_viewer::mesh_processing::half_edge::HalfEdgeMesh;
+///
+/// let mut mesh = Mesh::new();
+/// mesh.points = vec![
+///     Point3::from_array([0.0, 0.0, 0.0]),
+///     Point3::from_array([1.0, 0.0, 0.0]),
+///     Point3::from_array([1.0, 0.0, -1.0]),
+///     Point3::from_array([0.0, 0.0, -1.0]),
+/// ];
+/// // a square made of two triangles
+/// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 1.0, 0.0]));
+/// mesh.push_triangle([0, 2, 3], Point3::from_array([0.0, 1.0, 0.0]));
+///
+/// let half_edges = HalfEdgeMesh::new(&mesh);
+/// assert_eq!(half_edges.triangle_neighbours(0), vec![1]);
+/// assert_eq!(half_edges.boundary_loops(), vec![vec![0, 1, 2, 3]]);
+/// ```
+#[derive(Debug)]
+pub struct HalfEdgeMesh {
+    triangles: Vec<[usize; 3]>,
+    /// Sorted by their vertices, so that iterating over them gives the same order every time
+    edges: Vec<Edge>,
+    /// The edge of each half-edge, `None` if both ends are the same vertex
+    half_edge_edges: Vec<Option<usize>>,
+    outgoing_half_edges: Vec<Vec<usize>>,
+}
+
+impl HalfEdgeMesh {
+    pub fn new(mesh: &Mesh) -> Self {
+        let triangles: Vec<[usize; 3]> = (0..mesh.triangle_count())
+            .map(|triangle| mesh.triangle_indices(triangle))
+            .collect();
+
+        let mut outgoing_half_edges = vec![Vec::new(); mesh.points.len()];
+        let mut half_edges_per_edge: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (triangle_index, triangle) in triangles.iter().enumerate() {
+            for corner in 0..3 {
+                let half_edge = 3 * triangle_index + corner;
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                outgoing_half_edges[a].push(half_edge);
+                if a != b {
+                    half_edges_per_edge
+                        .entry((a.min(b), a.max(b)))
+                        .or_default()
+                        .push(half_edge);
+                }
+            }
+        }
+
+        let mut edges: Vec<Edge> = half_edges_per_edge
+            .into_iter()
+            .map(|(vertices, half_edges)| Edge {
+                vertices,
+                half_edges,
+            })
+            .collect();
+        edges.sort_unstable_by_key(|edge| edge.vertices);
+
+        let mut half_edge_edges = vec![None; 3 * triangles.len()];
+        for (edge_index, edge) in edges.iter().enumerate() {
+            for half_edge in &edge.half_edges {
+                half_edge_edges[*half_edge] = Some(edge_index);
+            }
+        }
+
+        HalfEdgeMesh {
+            triangles,
+            edges,
+            half_edge_edges,
+            outgoing_half_edges,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// The edge between two vertices, in any order
+    pub fn find_edge(&self, a: usize, b: usize) -> Option<&Edge> {
+        self.edges
+            .binary_search_by_key(&(a.min(b), a.max(b)), |edge| edge.vertices)
+            .ok()
+            .map(|edge_index| &self.edges[edge_index])
+    }
+
+    pub fn origin(&self, half_edge: usize) -> usize {
+        self.triangles[half_edge / 3][half_edge % 3]
+    }
+
+    pub fn target(&self, half_edge: usize) -> usize {
+        self.origin(self.next(half_edge))
+    }
+
+    pub fn triangle(&self, half_edge: usize) -> usize {
+        half_edge / 3
+    }
+
+    /// The next half-edge in the same triangle
+    pub fn next(&self, half_edge: usize) -> usize {
+        half_edge - half_edge % 3 + (half_edge + 1) % 3
+    }
+
+    /// The previous half-edge in the same triangle
+    pub fn previous(&self, half_edge: usize) -> usize {
+        half_edge - half_edge % 3 + (half_edge + 2) % 3
+    }
+
+    /// `None` for half-edges that are degenerate, because both ends are the same vertex
+    pub fn edge(&self, half_edge: usize) -> Option<&Edge> {
+        self.edge_index(half_edge)
+            .map(|edge_index| &self.edges[edge_index])
+    }
+
+    /// Index into `edges`, `None` for degenerate half-edges
+    pub fn edge_index(&self, half_edge: usize) -> Option<usize> {
+        self.half_edge_edges[half_edge]
+    }
+
+    /// The other half-edge of a manifold edge.
+    /// It runs in the opposite direction if both triangles have the same winding order.
+    pub fn twin(&self, half_edge: usize) -> Option<usize> {
+        let edge = self.edge(half_edge)?;
+        match edge.half_edges[..] {
+            [a, b] if a == half_edge => Some(b),
+            [a, b] if b == half_edge => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Half-edges that start at the vertex, one for each triangle corner at the vertex
+    pub fn outgoing_half_edges(&self, vertex: usize) -> &[usize] {
+        &self.outgoing_half_edges[vertex]
+    }
+
+    /// Triangles that use the vertex, sorted
+    pub fn vertex_triangles(&self, vertex: usize) -> Vec<usize> {
+        let mut triangles: Vec<usize> = self.outgoing_half_edges[vertex]
+            .iter()
+            .map(|half_edge| self.triangle(*half_edge))
+            .collect();
+        triangles.dedup();
+        triangles
+    }
+
+    /// The one-ring neighbourhood: vertices that share an edge with the vertex, sorted
+    pub fn vertex_neighbours(&self, vertex: usize) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = self.outgoing_half_edges[vertex]
+            .iter()
+            .flat_map(|half_edge| {
+                [
+                    self.target(*half_edge),
+                    self.origin(self.previous(*half_edge)),
+                ]
+            })
+            .filter(|neighbour| *neighbour != vertex)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    /// Triangles that share an edge with the triangle, sorted
+    pub fn triangle_neighbours(&self, triangle: usize) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = (3 * triangle..3 * triangle + 3)
+            .filter_map(|half_edge| self.edge(half_edge))
+            .flat_map(|edge| &edge.half_edges)
+            .map(|half_edge| self.triangle(*half_edge))
+            .filter(|neighbour| *neighbour != triangle)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    /// Corners at the same vertex in the triangles that share one of the two edges of the corner.
+    /// Corners are given by their outgoing half-edge.
+    pub fn adjacent_corners(&self, corner: usize) -> Vec<usize> {
+        let vertex = self.origin(corner);
+        [corner, self.previous(corner)]
+            .into_iter()
+            .filter_map(|half_edge| self.edge(half_edge))
+            .flat_map(|edge| &edge.half_edges)
+            .filter(|half_edge| self.triangle(**half_edge) != self.triangle(corner))
+            .map(|half_edge| {
+                if self.origin(*half_edge) == vertex {
+                    *half_edge
+                } else {
+                    self.next(*half_edge)
+                }
+            })
+            .collect()
+    }
+
+    /// A vertex is manifold if the triangles around it form a single fan,
+    /// connected through edges that are shared by at most two triangles.
+    pub fn is_manifold_vertex(&self, vertex: usize) -> bool {
+        let corners = &self.outgoing_half_edges[vertex];
+        let Some(first_corner) = corners.first() else {
+            return true;
+        };
+        let mut visited = vec![*first_corner];
+        let mut stack = vec![*first_corner];
+        while let Some(corner) = stack.pop() {
+            for half_edge in [corner, self.previous(corner)] {
+                if self.edge(half_edge).is_some_and(|edge| !edge.is_manifold()) {
+                    return false;
+                }
+            }
+            for neighbour in self.adjacent_corners(corner) {
+                if !visited.contains(&neighbour) {
+                    visited.push(neighbour);
+                    stack.push(neighbour);
+                }
+            }
+        }
+        visited.len() == corners.len()
+    }
+
+    /// All edges are shared by at most two triangles and all vertices are manifold
+    pub fn is_manifold(&self) -> bool {
+        self.edges.iter().all(Edge::is_manifold)
+            && (0..self.outgoing_half_edges.len()).all(|vertex| self.is_manifold_vertex(vertex))
+    }
+
+    /// The two triangles of every manifold edge run along it in opposite directions,
+    /// so that neighbouring triangles face the same side of the surface
+    pub fn is_consistently_wound(&self) -> bool {
+        (0..3 * self.triangles.len()).all(|half_edge| {
+            self.twin(half_edge)
+                .is_none_or(|twin| self.origin(twin) != self.origin(half_edge))
+        })
+    }
+
+    /// Closed chains of boundary edges, as vertex indices.
+    /// Each loop starts in the direction of its first half-edge.
+    /// Loops that touch at a vertex may be returned as one loop.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let boundary_edges: Vec<&Edge> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.is_boundary())
+            .collect();
+        let mut boundary_edges_per_vertex: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (index, edge) in boundary_edges.iter().enumerate() {
+            let (a, b) = edge.vertices;
+            boundary_edges_per_vertex.entry(a).or_default().push(index);
+            boundary_edges_per_vertex.entry(b).or_default().push(index);
+        }
+
+        let mut is_used = vec![false; boundary_edges.len()];
+        let mut loops = Vec::new();
+        for start_index in 0..boundary_edges.len() {
+            if is_used[start_index] {
+                continue;
+            }
+            is_used[start_index] = true;
+            let half_edge = boundary_edges[start_index].half_edges[0];
+            let mut boundary_loop = vec![self.origin(half_edge)];
+            let mut current = self.target(half_edge);
+
+            // the direction is not followed, so that loops with inconsistent winding are closed as well
+            while let Some(&next_index) = boundary_edges_per_vertex[&current]
+                .iter()
+                .find(|index| !is_used[**index])
+            {
+                is_used[next_index] = true;
+                boundary_loop.push(current);
+                let (a, b) = boundary_edges[next_index].vertices;
+                current = if a == current { b } else { a };
+            }
+            if current != boundary_loop[0] {
+                boundary_loop.push(current);
+            }
+            loops.push(boundary_loop);
+        }
+        loops
+    }
+
+    /// Groups of triangles that are connected through shared vertices
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        let mut is_visited = vec![false; self.triangles.len()];
+        let mut components = Vec::new();
+        for start_triangle in 0..self.triangles.len() {
+            if is_visited[start_triangle] {
+                continue;
+            }
+            is_visited[start_triangle] = true;
+            let mut component = vec![start_triangle];
+            let mut queue = VecDeque::from([start_triangle]);
+            while let Some(triangle) = queue.pop_front() {
+                for vertex in self.triangles[triangle] {
+                    for half_edge in &self.outgoing_half_edges[vertex] {
+                        let neighbour = self.triangle(*half_edge);
+                        if !is_visited[neighbour] {
+                            is_visited[neighbour] = true;
+                            component.push(neighbour);
+                            queue.push_back(neighbour);
+                        }
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HalfEdgeMesh;
+    use crate::general::positions_3d::{Mesh, Point as Point3};
+    use crate::mesh_processing::test_meshes::tetrahedron;
+
+    fn mesh_from_triangles(point_count: usize, triangles: &[[usize; 3]]) -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.points = (0..point_count)
+            .map(|i| Point3::from_array([i as f32, (i * i) as f32, 0.0]))
+            .collect();
+        for triangle in triangles {
+            mesh.push_triangle(*triangle, Point3::new());
+        }
+        mesh
+    }
+
+    #[test]
+    fn test_closed_mesh() {
+        let half_edges = HalfEdgeMesh::new(&tetrahedron());
+        assert_eq!(half_edges.edges().len(), 6);
+        assert!(half_edges.is_manifold());
+        assert!(half_edges.boundary_loops().is_empty());
+        assert_eq!(half_edges.vertex_neighbours(0), vec![1, 2, 3]);
+        assert_eq!(half_edges.vertex_triangles(3), vec![1, 2, 3]);
+        assert_eq!(half_edges.triangle_neighbours(0), vec![1, 2, 3]);
+        assert_eq!(half_edges.connected_components(), vec![vec![0, 1, 2, 3]]);
+
+        for half_edge in 0..12 {
+            let twin = half_edges.twin(half_edge).unwrap();
+            assert_eq!(half_edges.twin(twin), Some(half_edge));
+            assert_eq!(half_edges.origin(twin), half_edges.target(half_edge));
+        }
+    }
+
+    #[test]
+    fn test_boundary_loop_of_open_mesh() {
+        let mut mesh = tetrahedron();
+        mesh.retain_triangles(&[true, true, true, false]);
+        let half_edges = HalfEdgeMesh::new(&mesh);
+        assert!(half_edges.is_manifold());
+        assert_eq!(half_edges.find_edge(2, 1).unwrap().half_edges, vec![1]);
+        assert_eq!(half_edges.twin(1), None);
+        assert_eq!(half_edges.boundary_loops(), vec![vec![2, 1, 3]]);
+    }
+
+    #[test]
+    fn test_non_manifold_edge() {
+        // three triangles around the edge between vertex 0 and 1
+        let mesh = mesh_from_triangles(5, &[[0, 1, 2], [1, 0, 3], [1, 0, 4]]);
+        let half_edges = HalfEdgeMesh::new(&mesh);
+        let edge = half_edges.find_edge(0, 1).unwrap();
+        assert!(!edge.is_manifold());
+        assert_eq!(half_edges.twin(edge.half_edges[0]), None);
+        assert!(!half_edges.is_manifold_vertex(0));
+        assert!(half_edges.is_manifold_vertex(2));
+    }
+
+    #[test]
+    fn test_triangles_touching_at_one_vertex() {
+        // two triangles that only share vertex 0
+        let mesh = mesh_from_triangles(6, &[[0, 1, 2], [0, 3, 4]]);
+        let half_edges = HalfEdgeMesh::new(&mesh);
+        assert!(half_edges.edges().iter().all(|edge| edge.is_manifold()));
+        assert!(!half_edges.is_manifold_vertex(0));
+        assert!(!half_edges.is_manifold());
+        assert!(half_edges.is_manifold_vertex(5));
+        assert_eq!(half_edges.connected_components().len(), 1);
+    }
+}
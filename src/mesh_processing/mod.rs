@@ -0,0 +1,15 @@
+pub mod axis_conversion;
+pub mod bvh;
+pub mod decimation;
+pub mod deviation;
+pub mod explode;
+pub mod half_edge;
+pub mod normals;
+pub mod repair;
+pub mod section;
+pub mod statistics;
+pub mod subdivision;
+#[cfg(test)]
+pub(crate) mod test_meshes;
+pub mod transform;
+pub mod welding;
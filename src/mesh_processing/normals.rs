@@ -0,0 +1,223 @@
+use crate::general::positions_3d::{
+    cross_product, dot_product, to_mesh_index, Degrees, Mesh, Point as Point3,
+    Triangle as Triangle3,
+};
+use crate::mesh_processing::half_edge::HalfEdgeMesh;
+use std::collections::HashMap;
+
+/// Decides how much each triangle contributes to the normals of its vertices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NormalWeighting {
+    /// Large triangles contribute more than small ones.
+    Area,
+    /// Triangles contribute by the angle of their corner at the vertex,
+    /// which does not depend on how the surface was tessellated.
+    Angle,
+}
+
+impl Mesh {
+    /// Computes smooth vertex normals and stores them in `Mesh::normals`.
+    /// Existing vertex normals are replaced.
+    ///
+    /// Triangles that share an edge are smoothed together
+    /// if the angle between their normals is at most `crease_angle`.
+    /// Sharper edges are kept hard by giving each side its own vertex normal.
+    ///
+    /// # Example
+    /// ```
+    /// use rust_obj_terminal_viewer::general::positions_3d::{Degrees, Mesh, Point as Point3};
+    /// use rust_obj_terminal_viewer::mesh_processing::normals::NormalWeighting;
+    ///
+    /// let mut mesh = Mesh::new();
+    /// mesh.points = vec![
+    ///     Point3::from_array([0.0, 0.0, 0.0]),
+    ///     Point3::from_array([1.0, 0.0, 0.0]),
+    ///     Point3::from_array([0.0, 1.0, 0.0]),
+    ///     Point3::from_array([0.0, 0.0, -1.0]),
+    /// ];
+    /// // two triangles with a 90 degree edge between them
+    /// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 0.0, 1.0]));
+    /// mesh.push_triangle([0, 1, 3], Point3::from_array([0.0, 1.0, 0.0]));
+    ///
+    /// mesh.generate_vertex_normals(Degrees(30.0), NormalWeighting::Angle);
+    /// assert_eq!(mesh.normals.len(), 6);
+    ///
+    /// mesh.generate_vertex_normals(Degrees(100.0), NormalWeighting::Angle);
+    /// assert_eq!(mesh.normals.len(), 4);
+    /// ```
+    pub fn generate_vertex_normals(&mut self, crease_angle: Degrees, weighting: NormalWeighting) {
+        let min_cos = crease_angle.0.to_radians().cos();
+        let face_normals: Vec<Point3> = (0..self.triangle_count())
+            .map(|triangle| Triangle3::get_normal(&self.triangle_points(triangle)))
+            .collect();
+
+        let half_edges = HalfEdgeMesh::new(self);
+
+        let mut normals = Vec::new();
+        let mut normal_indices = vec![[0; 3]; self.triangle_count()];
+        for vertex_index in 0..self.points.len() {
+            let smoothing_groups =
+                smoothing_groups(&half_edges, vertex_index, &face_normals, min_cos);
+            for group in smoothing_groups {
+                let normal = group
+                    .iter()
+                    // degenerate triangles have a NaN normal
+                    .filter(|(triangle_index, _)| face_normals[*triangle_index].x.is_finite())
+                    .map(|&(triangle_index, corner)| {
+                        let weight = self.corner_weight(triangle_index, corner, weighting);
+                        face_normals[triangle_index].map(|component| component * weight)
+                    })
+                    .fold(Point3::new(), |sum, normal| sum.add(&normal));
+                let normal = if normal == Point3::new() {
+                    // all triangles in the group are degenerate
+                    normal
+                } else {
+                    normal.normalized()
+                };
+
+                normals.push(normal);
+                for (triangle_index, corner) in group {
+                    normal_indices[triangle_index][corner] = to_mesh_index(normals.len() - 1);
+                }
+            }
+        }
+
+        self.normals = normals;
+        self.face_data.normal_indices = Some(normal_indices);
+    }
+
+    fn corner_weight(
+        &self,
+        triangle_index: usize,
+        corner: usize,
+        weighting: NormalWeighting,
+    ) -> f32 {
+        let points = self.triangle_points(triangle_index);
+        match weighting {
+            NormalWeighting::Area => {
+                let a = points[1].relative_to(points[0]);
+                let b = points[2].relative_to(points[0]);
+                let cross = cross_product(a, b);
+                dot_product(&cross, &cross).sqrt() / 2.0
+            }
+            NormalWeighting::Angle => {
+                let corner_point = points[corner];
+                let a = points[(corner + 1) % 3].relative_to(corner_point);
+                let b = points[(corner + 2) % 3].relative_to(corner_point);
+                let lengths = dot_product(&a, &a).sqrt() * dot_product(&b, &b).sqrt();
+                if lengths == 0.0 {
+                    return 0.0;
+                }
+                (dot_product(&a, &b) / lengths).clamp(-1.0, 1.0).acos()
+            }
+        }
+    }
+}
+
+/// Splits the triangle corners around a vertex into groups that should share a normal.
+/// Two triangles end up in the same group if they are connected through
+/// edges around the vertex that are not sharper than the crease angle.
+/// Corners are returned as triangle index and corner index.
+fn smoothing_groups(
+    half_edges: &HalfEdgeMesh,
+    vertex_index: usize,
+    face_normals: &[Point3],
+    min_cos: f32,
+) -> Vec<Vec<(usize, usize)>> {
+    // corners are identified by their outgoing half-edge
+    let corners = half_edges.outgoing_half_edges(vertex_index);
+    let mut parents: Vec<usize> = (0..corners.len()).collect();
+
+    for (corner_a, half_edge) in corners.iter().enumerate() {
+        for adjacent_half_edge in half_edges.adjacent_corners(*half_edge) {
+            let normal_a = &face_normals[half_edges.triangle(*half_edge)];
+            let normal_b = &face_normals[half_edges.triangle(adjacent_half_edge)];
+            if dot_product(normal_a, normal_b) >= min_cos {
+                let corner_b = corners
+                    .iter()
+                    .position(|corner| *corner == adjacent_half_edge)
+                    .expect("adjacent corners are at the same vertex");
+                let root_a = find_root(&mut parents, corner_a);
+                let root_b = find_root(&mut parents, corner_b);
+                parents[root_a] = root_b;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for (corner_i, half_edge) in corners.iter().enumerate() {
+        let root = find_root(&mut parents, corner_i);
+        groups
+            .entry(root)
+            .or_default()
+            .push((half_edges.triangle(*half_edge), half_edge % 3));
+    }
+    let mut groups: Vec<Vec<(usize, usize)>> = groups.into_values().collect();
+    // HashMap order is random, sort to get the same normal order every time
+    groups.sort_by_key(|group| group[0]);
+    groups
+}
+
+fn find_root(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[index] = root;
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NormalWeighting;
+    use crate::general::positions_3d::{Degrees, Mesh, Point as Point3};
+
+    /// A flat square split into two triangles with very different areas at vertex 0.
+    fn flat_mesh() -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.0, 0.0, 0.0]),
+            Point3::from_array([4.0, 0.0, 0.0]),
+            Point3::from_array([4.0, 1.0, 0.0]),
+            Point3::from_array([0.0, 1.0, 0.0]),
+        ];
+        let normal = Point3::from_array([0.0, 0.0, 1.0]);
+        mesh.push_triangle([0, 1, 2], normal.clone());
+        mesh.push_triangle([0, 2, 3], normal);
+        mesh
+    }
+
+    #[test]
+    fn test_flat_surface_gets_shared_normals() {
+        for weighting in [NormalWeighting::Area, NormalWeighting::Angle] {
+            let mut mesh = flat_mesh();
+            mesh.generate_vertex_normals(Degrees(10.0), weighting);
+            assert_eq!(mesh.normals.len(), 4);
+            assert!(mesh
+                .normals
+                .iter()
+                .all(|normal| *normal == Point3::from_array([0.0, 0.0, 1.0])));
+            assert_eq!(mesh.normal_indices(1), Some([0, 2, 3]));
+        }
+    }
+
+    #[test]
+    fn test_crease_edge_normals_are_averaged_when_below_crease_angle() {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.0, 0.0, 0.0]),
+            Point3::from_array([1.0, 0.0, 0.0]),
+            Point3::from_array([0.0, 1.0, 0.0]),
+            Point3::from_array([0.0, 0.0, -1.0]),
+        ];
+        mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 0.0, 1.0]));
+        mesh.push_triangle([0, 1, 3], Point3::from_array([0.0, 1.0, 0.0]));
+        mesh.generate_vertex_normals(Degrees(100.0), NormalWeighting::Area);
+
+        let shared_normal_index = mesh.normal_indices(0).unwrap()[0];
+        let shared_normal = &mesh.normals[shared_normal_index];
+        let expected = Point3::from_array([0.0, 1.0, 1.0]).normalized();
+        assert!((shared_normal.y - expected.y).abs() < 1e-6);
+        assert!((shared_normal.z - expected.z).abs() < 1e-6);
+    }
+}
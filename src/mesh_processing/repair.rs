@@ -0,0 +1,288 @@
+use crate::general::positions_3d::{
+    cross_product, dot_product, to_mesh_index, Mesh, Point as Point3, Triangle as Triangle3,
+};
+use crate::mesh_processing::half_edge::HalfEdgeMesh;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// What `Mesh::repair` changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct RepairReport {
+    /// Vertices with NaN or infinite coordinates
+    pub removed_invalid_vertices: usize,
+    /// Triangles that used one of the removed vertices
+    pub removed_triangles_with_invalid_vertices: usize,
+    /// Triangles with repeated vertices or without area
+    pub removed_degenerate_triangles: usize,
+    /// Triangles that use the same three vertices as an earlier triangle
+    pub removed_duplicate_triangles: usize,
+    /// Triangles whose winding order was reversed to match their neighbours
+    pub flipped_triangles: usize,
+}
+
+impl Mesh {
+    /// Removes invalid vertices, degenerate triangles and duplicate triangles,
+    /// and gives all triangles a consistent winding order.
+    ///
+    /// The winding order is propagated across shared edges, separately for each connected component.
+    /// Each component is then turned outwards, using the sign of its enclosed volume.
+    /// Finally all triangle normals are recomputed from the winding order.
+    pub fn repair(&mut self) -> RepairReport {
+        let mut report = RepairReport::default();
+        self.remove_invalid_vertices(&mut report);
+        self.remove_degenerate_and_duplicate_triangles(&mut report);
+        report.flipped_triangles = self.orient_consistently();
+
+        self.face_normals = (0..self.triangle_count())
+            .map(|triangle| Triangle3::get_normal(&self.triangle_points(triangle)))
+            .collect();
+        report
+    }
+
+    fn remove_invalid_vertices(&mut self, report: &mut RepairReport) {
+        let is_valid: Vec<bool> = self
+            .points
+            .iter()
+            .map(|point| {
+                point
+                    .to_array()
+                    .iter()
+                    .all(|component| component.is_finite())
+            })
+            .collect();
+
+        let keep: Vec<bool> = self
+            .triangles
+            .iter()
+            .map(|triangle| triangle.iter().all(|index| is_valid[*index as usize]))
+            .collect();
+        report.removed_triangles_with_invalid_vertices = keep.iter().filter(|keep| !**keep).count();
+        self.retain_triangles(&keep);
+
+        let mut new_indices = vec![u32::MAX; self.points.len()];
+        let mut points = Vec::with_capacity(self.points.len());
+        for (index, point) in self.points.drain(..).enumerate() {
+            if is_valid[index] {
+                new_indices[index] = to_mesh_index(points.len());
+                points.push(point);
+            }
+        }
+        report.removed_invalid_vertices = new_indices.len() - points.len();
+        self.points = points;
+
+        for triangle in &mut self.triangles {
+            *triangle = triangle.map(|index| new_indices[index as usize]);
+        }
+    }
+
+    fn remove_degenerate_and_duplicate_triangles(&mut self, report: &mut RepairReport) {
+        let mut seen_vertex_sets = HashSet::new();
+        let mut removed_degenerate_triangles = 0;
+        let mut removed_duplicate_triangles = 0;
+
+        let keep: Vec<bool> = (0..self.triangle_count())
+            .map(|triangle_index| {
+                if self.is_degenerate_triangle(triangle_index) {
+                    removed_degenerate_triangles += 1;
+                    return false;
+                }
+                let mut vertex_set = self.triangles[triangle_index];
+                vertex_set.sort_unstable();
+                if !seen_vertex_sets.insert(vertex_set) {
+                    removed_duplicate_triangles += 1;
+                    return false;
+                }
+                true
+            })
+            .collect();
+        self.retain_triangles(&keep);
+
+        report.removed_degenerate_triangles = removed_degenerate_triangles;
+        report.removed_duplicate_triangles = removed_duplicate_triangles;
+    }
+
+    /// Returns the amount of triangles that were flipped
+    fn orient_consistently(&mut self) -> usize {
+        let half_edges = HalfEdgeMesh::new(self);
+        let mut is_visited = vec![false; self.triangle_count()];
+        let mut should_flip = vec![false; self.triangle_count()];
+        for start_index in 0..self.triangle_count() {
+            if is_visited[start_index] {
+                continue;
+            }
+            is_visited[start_index] = true;
+            let mut component = vec![start_index];
+            let mut queue = VecDeque::from([start_index]);
+
+            while let Some(triangle_index) = queue.pop_front() {
+                for half_edge in 3 * triangle_index..3 * triangle_index + 3 {
+                    // the orientation can not be propagated over boundary and non-manifold edges
+                    let Some(twin) = half_edges.twin(half_edge) else {
+                        continue;
+                    };
+                    let neighbour_index = half_edges.triangle(twin);
+                    if is_visited[neighbour_index] {
+                        continue;
+                    }
+                    // Consistently wound neighbours traverse the shared edge in opposite directions
+                    let has_same_direction =
+                        half_edges.origin(twin) == half_edges.origin(half_edge);
+                    should_flip[neighbour_index] = should_flip[triangle_index] ^ has_same_direction;
+                    is_visited[neighbour_index] = true;
+                    component.push(neighbour_index);
+                    queue.push_back(neighbour_index);
+                }
+            }
+
+            if self.signed_volume(&component, &should_flip) < 0.0 {
+                for triangle_index in component {
+                    should_flip[triangle_index] = !should_flip[triangle_index];
+                }
+            }
+        }
+
+        let mut reversed_normals = HashMap::new();
+        for (triangle, should_flip) in should_flip.iter().enumerate() {
+            if *should_flip {
+                self.flip_winding(triangle);
+                self.reverse_vertex_normals(triangle, &mut reversed_normals);
+            }
+        }
+        should_flip
+            .iter()
+            .filter(|should_flip| **should_flip)
+            .count()
+    }
+
+    /// Makes the vertex normals of a flipped triangle point to its new front side.
+    /// Other triangles can share the normals, so the triangle gets reversed copies of them,
+    /// which `reversed_normals` maps to from the original normal indices
+    fn reverse_vertex_normals(
+        &mut self,
+        triangle: usize,
+        reversed_normals: &mut HashMap<usize, usize>,
+    ) {
+        let Some(normal_indices) = self.normal_indices(triangle) else {
+            return;
+        };
+        let reversed_indices = normal_indices.map(|index| {
+            *reversed_normals.entry(index).or_insert_with(|| {
+                let reversed = self.normals[index].to_array().map(|coordinate| -coordinate);
+                self.normals.push(Point3::from_array(reversed));
+                self.normals.len() - 1
+            })
+        });
+        self.set_normal_indices(triangle, reversed_indices);
+    }
+
+    /// Volume enclosed by the triangles, as if the triangles in `should_flip` were flipped.
+    /// Negative if the triangles face inwards.
+    fn signed_volume(&self, triangle_indices: &[usize], should_flip: &[bool]) -> f32 {
+        // measuring relative to a point close to the triangles reduces floating point errors
+        let reference = self.triangle_points(triangle_indices[0])[0];
+        triangle_indices
+            .iter()
+            .map(|triangle_index| {
+                let [p1, p2, p3] = self
+                    .triangle_points(*triangle_index)
+                    .map(|point| point.relative_to(reference));
+                let volume = dot_product(&p1, &cross_product(p2, p3)) / 6.0;
+                if should_flip[*triangle_index] {
+                    -volume
+                } else {
+                    volume
+                }
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RepairReport;
+    use crate::general::positions_3d::{dot_product, Point as Point3};
+    use crate::mesh_processing::test_meshes::tetrahedron;
+
+    #[test]
+    fn test_repair_keeps_correct_mesh() {
+        let mut mesh = tetrahedron();
+        assert_eq!(mesh.repair(), RepairReport::default());
+        assert_eq!(mesh.face_normals[0], Point3::from_array([0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn test_repair_flips_inconsistent_triangle() {
+        let mut mesh = tetrahedron();
+        mesh.flip_winding(3);
+        assert_eq!(mesh.repair().flipped_triangles, 1);
+        assert_eq!(mesh.triangles[3], [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_repair_reverses_the_vertex_normals_of_flipped_triangles() {
+        let mut mesh = tetrahedron();
+        // smooth normals pointing away from the center, shared by all triangles
+        mesh.normals = mesh
+            .points
+            .iter()
+            .map(|point| {
+                point
+                    .relative_to(&Point3::from_array([0.25; 3]))
+                    .normalized()
+            })
+            .collect();
+        for triangle in 0..4 {
+            let indices = mesh.triangle_indices(triangle);
+            mesh.set_normal_indices(triangle, indices);
+        }
+        // a file where the last triangle is wound the other way, with normals pointing inwards
+        mesh.flip_winding(3);
+        let inward_normals: Vec<Point3> = mesh
+            .normals
+            .iter()
+            .map(|normal| Point3::from_array(normal.to_array().map(|coordinate| -coordinate)))
+            .collect();
+        mesh.normals.extend(inward_normals);
+        mesh.set_normal_indices(3, [5, 7, 6]);
+
+        mesh.repair();
+        for triangle in 0..4 {
+            let face_normal = &mesh.face_normals[triangle];
+            for index in mesh.normal_indices(triangle).unwrap() {
+                assert!(dot_product(&mesh.normals[index], face_normal) > 0.0);
+            }
+        }
+        // the normals of the other triangles are not changed
+        assert_eq!(mesh.normal_indices(0), Some([0, 2, 1]));
+    }
+
+    #[test]
+    fn test_repair_turns_inside_out_mesh_outwards() {
+        let mut mesh = tetrahedron();
+        for triangle in 0..4 {
+            mesh.flip_winding(triangle);
+        }
+        assert_eq!(mesh.repair().flipped_triangles, 4);
+        assert_eq!(mesh.face_normals[0], Point3::from_array([0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn test_repair_removes_broken_geometry() {
+        let mut mesh = tetrahedron();
+        mesh.points.push(Point3::from_array([f32::NAN, 0.0, 0.0]));
+        mesh.points.push(Point3::from_array([2.0, 0.0, 0.0]));
+        let normal = Point3::new();
+        // the last but one triangle is almost a line
+        mesh.points.push(Point3::from_array([3.0, 1e-9, 0.0]));
+        for triangle in [[0, 1, 4], [0, 1, 5], [0, 0, 2], [0, 6, 5], [2, 1, 0]] {
+            mesh.push_triangle(triangle, normal.clone());
+        }
+
+        let report = mesh.repair();
+        assert_eq!(report.removed_invalid_vertices, 1);
+        assert_eq!(report.removed_triangles_with_invalid_vertices, 1);
+        assert_eq!(report.removed_degenerate_triangles, 3);
+        assert_eq!(report.removed_duplicate_triangles, 1);
+        assert_eq!(mesh.points.len(), 6);
+        assert_eq!(mesh.triangle_count(), 4);
+    }
+}
@@ -0,0 +1,399 @@
+use crate::general::positions_2d::Point as Point2;
+use crate::general::positions_3d::{
+    cross_product, distance, dot_product, GlobalPoint, Mesh, Point as Point3,
+};
+use std::collections::HashMap;
+
+/// An infinite plane through `point`, facing towards `normal`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plane {
+    pub point: Point3,
+    /// Expected to be a unit vector
+    pub normal: Point3,
+}
+
+impl Plane {
+    pub fn signed_distance(&self, point: &Point3) -> f32 {
+        dot_product(&point.relative_to(&self.point), &self.normal)
+    }
+
+    /// Two unit vectors in the plane, so that right × up = normal.
+    ///
+    /// Up is +Y, unless the plane is horizontal. Then up is -Z,
+    /// which shows a horizontal section as seen from above.
+    pub fn right_and_up(&self) -> (Point3, Point3) {
+        let helper = if self.normal.y.abs() > 0.9 {
+            Point3::from_array([0.0, 0.0, -1.0])
+        } else {
+            Point3::from_array([0.0, 1.0, 0.0])
+        };
+        let along_normal = dot_product(&helper, &self.normal);
+        let up = helper
+            .relative_to(&self.normal.map(|component| component * along_normal))
+            .normalized();
+        let right = cross_product(up.clone(), self.normal.clone());
+        (right, up)
+    }
+}
+
+/// A chain of intersection points between a plane and a mesh.
+#[derive(Debug)]
+pub struct Contour {
+    pub points: Vec<Point3>,
+    /// False if the chain ended at a hole or at the border of the mesh
+    pub is_closed: bool,
+}
+
+/// The contours where a plane cuts through a mesh.
+#[derive(Debug)]
+pub struct Section {
+    pub plane: Plane,
+    pub contours: Vec<Contour>,
+    /// The `Mesh::origin` of the cut mesh, which the plane and the contours are relative to
+    pub origin: GlobalPoint,
+}
+
+impl Mesh {
+    /// Intersects all triangles with the plane and chains the segments into contours.
+    ///
+    /// Vertices that lie exactly on the plane are treated as if they were slightly in front of it,
+    /// so that every triangle is cut by at most one segment and the contours stay connected.
+    ///
+    /// # Example
+    /// ```
+    /// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+    /// use rust_obj_terminal_viewer::mesh_processing::section::Plane;
+    ///
+    /// let mut mesh = Mesh::new();
+    /// mesh.points = vec![
+    ///     Point3::from_array([0.0, 0.0, 0.0]),
+    ///     Point3::from_array([1.0, 0.0, 0.0]),
+    ///     Point3::from_array([0.0, 1.0, 0.0]),
+    /// ];
+    /// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 0.0, 1.0]));
+    ///
+    /// let plane = Plane {
+    ///     point: Point3::from_array([0.0, 0.5, 0.0]),
+    ///     normal: Point3::from_array([0.0, 1.0, 0.0]),
+    /// };
+    /// let section = mesh.section(&plane);
+    /// assert_eq!(section.contours.len(), 1);
+    /// assert_eq!(section.contours[0].points.len(), 2);
+    /// assert!(!section.contours[0].is_closed);
+    /// ```
+    pub fn section(&self, plane: &Plane) -> Section {
+        let distances: Vec<f32> = self
+            .points
+            .iter()
+            .map(|point| plane.signed_distance(point))
+            .collect();
+
+        // every cut mesh edge gives one intersection point, shared by the triangles on both sides
+        let mut point_indices: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut points = Vec::new();
+        let mut segments = Vec::new();
+        for triangle in 0..self.triangle_count() {
+            let indices = self.triangle_indices(triangle);
+            let mut segment = Vec::with_capacity(2);
+            for corner in 0..3 {
+                let (a, b) = (indices[corner], indices[(corner + 1) % 3]);
+                if (distances[a] >= 0.0) == (distances[b] >= 0.0) {
+                    continue;
+                }
+                let point_index = *point_indices
+                    .entry((a.min(b), a.max(b)))
+                    .or_insert_with(|| {
+                        let t = distances[a] / (distances[a] - distances[b]);
+                        let direction = self.points[b].relative_to(&self.points[a]);
+                        points.push(self.points[a].add(&direction.map(|component| component * t)));
+                        points.len() - 1
+                    });
+                segment.push(point_index);
+            }
+            // NaN distances can make a triangle cross the plane only once
+            if let [a, b] = segment[..] {
+                segments.push((a, b));
+            }
+        }
+
+        let contours = chain_segments(points.len(), &segments)
+            .into_iter()
+            .map(|(indices, is_closed)| {
+                let mut contour_points: Vec<Point3> = Vec::with_capacity(indices.len());
+                for index in indices {
+                    // cuts through vertices on the plane create the same point more than once
+                    if contour_points.last() != Some(&points[index]) {
+                        contour_points.push(points[index].clone());
+                    }
+                }
+                if is_closed
+                    && contour_points.len() > 1
+                    && contour_points.first() == contour_points.last()
+                {
+                    contour_points.pop();
+                }
+                Contour {
+                    points: contour_points,
+                    is_closed,
+                }
+            })
+            .filter(|contour| contour.points.len() > 1)
+            .collect();
+
+        Section {
+            plane: plane.clone(),
+            contours,
+            origin: self.origin,
+        }
+    }
+}
+
+/// Connects segments that share points into chains of point indices.
+/// Returns each chain together with whether it is closed.
+fn chain_segments(point_count: usize, segments: &[(usize, usize)]) -> Vec<(Vec<usize>, bool)> {
+    let mut segments_per_point = vec![Vec::new(); point_count];
+    for (segment_index, (a, b)) in segments.iter().enumerate() {
+        segments_per_point[*a].push(segment_index);
+        segments_per_point[*b].push(segment_index);
+    }
+
+    // open chains have to start at one of their ends, so those are tried first
+    let mut start_points: Vec<usize> = (0..point_count)
+        .filter(|point| segments_per_point[*point].len() % 2 == 1)
+        .collect();
+    start_points.extend(0..point_count);
+
+    let mut is_used = vec![false; segments.len()];
+    let mut chains = Vec::new();
+    for start_point in start_points {
+        let mut chain = vec![start_point];
+        let mut current = start_point;
+        while let Some(&segment_index) = segments_per_point[current]
+            .iter()
+            .find(|segment_index| !is_used[**segment_index])
+        {
+            is_used[segment_index] = true;
+            let (a, b) = segments[segment_index];
+            current = if a == current { b } else { a };
+            chain.push(current);
+        }
+        if chain.len() > 1 {
+            let is_closed = current == start_point;
+            if is_closed {
+                chain.pop();
+            }
+            chains.push((chain, is_closed));
+        }
+    }
+    chains
+}
+
+impl Section {
+    /// The contours in the coordinate system of the plane, see `Plane::right_and_up`.
+    /// The origin is `Plane::point`, distances are kept.
+    pub fn contours_2d(&self) -> Vec<Vec<Point2>> {
+        let (right, up) = self.plane.right_and_up();
+        self.contours
+            .iter()
+            .map(|contour| {
+                contour
+                    .points
+                    .iter()
+                    .map(|point| {
+                        let relative = point.relative_to(&self.plane.point);
+                        Point2 {
+                            x: dot_product(&relative, &right),
+                            y: dot_product(&relative, &up),
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The straight pieces of all contours, including the closing piece of closed contours
+    pub fn lines(&self) -> Vec<[Point3; 2]> {
+        let mut lines = Vec::new();
+        for contour in &self.contours {
+            for pair in contour.points.windows(2) {
+                lines.push([pair[0].clone(), pair[1].clone()]);
+            }
+            if let (true, Some(first), Some(last)) = (
+                contour.is_closed,
+                contour.points.first(),
+                contour.points.last(),
+            ) {
+                lines.push([last.clone(), first.clone()]);
+            }
+        }
+        lines
+    }
+
+    /// Total length of all contours
+    pub fn length(&self) -> f32 {
+        self.lines()
+            .iter()
+            .map(|[start, end]| distance(start, end))
+            .sum()
+    }
+
+    /// An SVG image of the contours, in the units of the mesh.
+    /// Like the DXF drawing it uses the original coordinates projected onto the plane.
+    /// The Y axis points up like in the plane, SVG itself has Y pointing down.
+    pub fn to_svg(&self) -> String {
+        let contours = self.global_contours_2d();
+        let (mut min, mut max) = ([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]);
+        for [x, y] in contours.iter().flatten() {
+            min = [min[0].min(*x), min[1].min(*y)];
+            max = [max[0].max(*x), max[1].max(*y)];
+        }
+        if contours.iter().all(Vec::is_empty) {
+            (min, max) = ([0.0; 2], [0.0; 2]);
+        }
+        let margin = (max[0] - min[0]).max(max[1] - min[1]) * 0.05;
+        let (x, y) = (min[0] - margin, -max[1] - margin);
+        let (width, height) = (
+            max[0] - min[0] + 2.0 * margin,
+            max[1] - min[1] + 2.0 * margin,
+        );
+
+        let paths: Vec<String> = contours
+            .iter()
+            .zip(&self.contours)
+            .map(|(points, contour)| {
+                let commands: Vec<String> = points
+                    .iter()
+                    .enumerate()
+                    .map(|(i, [x, y])| {
+                        let command = if i == 0 { "M" } else { "L" };
+                        format!("{command}{x} {}", -y)
+                    })
+                    .collect();
+                let close = if contour.is_closed { " Z" } else { "" };
+                format!("  <path d=\"{}{close}\"/>", commands.join(" "))
+            })
+            .collect();
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x} {y} {width} {height}\">\n\
+            <g fill=\"none\" stroke=\"black\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\">\n\
+            {}\n\
+            </g>\n\
+            </svg>\n",
+            paths.join("\n")
+        )
+    }
+
+    /// An ASCII DXF drawing (AutoCAD R12) with one polyline per contour, in the units of the mesh.
+    /// Unlike `contours_2d`, the drawing uses the original coordinates projected onto the plane,
+    /// so that sections of georeferenced meshes keep their position.
+    pub fn to_dxf(&self) -> String {
+        let mut lines: Vec<String> = ["0", "SECTION", "2", "ENTITIES"].map(String::from).to_vec();
+        for (points, contour) in self.global_contours_2d().iter().zip(&self.contours) {
+            let flags = if contour.is_closed { "1" } else { "0" };
+            lines.extend(["0", "POLYLINE", "8", "0", "66", "1", "70", flags].map(String::from));
+            lines.extend(["10", "0", "20", "0", "30", "0"].map(String::from));
+            for [x, y] in points {
+                lines.extend(["0", "VERTEX", "8", "0", "10"].map(String::from));
+                lines.push(x.to_string());
+                lines.push(String::from("20"));
+                lines.push(y.to_string());
+                lines.extend(["30", "0"].map(String::from));
+            }
+            lines.extend(["0", "SEQEND", "8", "0"].map(String::from));
+        }
+        lines.extend(["0", "ENDSEC", "0", "EOF"].map(String::from));
+        lines.join("\n") + "\n"
+    }
+
+    /// The contours in the original coordinates of the mesh, projected onto the right and up axes of the plane.
+    fn global_contours_2d(&self) -> Vec<Vec<[f64; 2]>> {
+        let (right, up) = self.plane.right_and_up();
+        self.contours
+            .iter()
+            .map(|contour| {
+                contour
+                    .points
+                    .iter()
+                    .map(|point| {
+                        let global = self.origin.add_local(point);
+                        [global.dot_product(&right), global.dot_product(&up)]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Plane;
+    use crate::general::positions_3d::{GlobalPoint, Point as Point3};
+    use crate::mesh_processing::test_meshes::cube;
+
+    fn horizontal_plane(height: f32) -> Plane {
+        Plane {
+            point: Point3::from_array([0.0, height, 0.0]),
+            normal: Point3::from_array([0.0, 1.0, 0.0]),
+        }
+    }
+
+    #[test]
+    fn test_section_of_cube_is_one_closed_square() {
+        let section = cube().section(&horizontal_plane(0.5));
+        assert_eq!(section.contours.len(), 1);
+        assert!(section.contours[0].is_closed);
+        assert!(section.contours[0]
+            .points
+            .iter()
+            .all(|point| point.y == 0.5));
+        assert!((section.length() - 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_section_through_vertices_stays_closed() {
+        // a diagonal plane through four of the cube corners
+        let plane = Plane {
+            point: Point3::from_array([1.0, 0.0, 0.0]),
+            normal: Point3::from_array([1.0, 1.0, 0.0]).normalized(),
+        };
+        let section = cube().section(&plane);
+        assert_eq!(section.contours.len(), 1);
+        assert!(section.contours[0].is_closed);
+        assert!((section.length() - (2.0 + 2.0 * 2.0_f32.sqrt())).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_plane_that_misses_the_mesh() {
+        let section = cube().section(&horizontal_plane(2.0));
+        assert!(section.contours.is_empty());
+        assert!(section.to_svg().starts_with("<svg"));
+    }
+
+    #[test]
+    fn test_horizontal_section_is_seen_from_above() {
+        let section = cube().section(&horizontal_plane(0.5));
+        let points = section.contours_2d().concat();
+        // -z is up in the drawing
+        assert!(points.iter().all(|point| (0.0..=1.0).contains(&point.x)));
+        assert!(points.iter().all(|point| (-1.0..=0.0).contains(&point.y)));
+
+        let dxf = section.to_dxf();
+        assert_eq!(dxf.matches("VERTEX").count(), points.len());
+        assert!(dxf.ends_with("EOF\n"));
+    }
+
+    #[test]
+    fn test_exports_use_global_coordinates() {
+        let mut mesh = cube();
+        mesh.origin = GlobalPoint::from_array([2_500_000.0, 0.0, 0.0]);
+        let section = mesh.section(&horizontal_plane(0.5));
+        let dxf = section.to_dxf();
+        assert!(dxf.contains("\n2500001\n"));
+        assert!(!dxf.contains("\n1\n20"));
+
+        let svg = section.to_svg();
+        assert!(svg.contains("2500000 ") && svg.contains("2500001 "));
+        assert!(!svg.contains("M0 ") && !svg.contains("L1 "));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::general::positions_3d::{
+    cross_product, dot_product, BoundingBox, GlobalPoint, Mesh, Point as Point3,
+};
+use crate::mesh_processing::half_edge::HalfEdgeMesh;
+use std::fmt::Display;
+
+/// Counts, measurements and topology of a mesh, as reported by the `info` command.
+#[derive(Debug)]
+pub struct MeshStatistics {
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    pub normal_count: usize,
+    pub uv_count: usize,
+    pub group_count: usize,
+    pub material_count: usize,
+    /// In the original coordinates of the file, see `Mesh::origin`
+    pub bounding_box_min: GlobalPoint,
+    pub bounding_box_max: GlobalPoint,
+    pub surface_area: f32,
+    /// Only meaningful if the mesh is watertight and consistently wound, see `has_volume`.
+    /// Negative if the triangles are wound inside out.
+    pub volume: f32,
+    pub is_watertight: bool,
+    /// Neighbouring triangles face the same side of the surface, `repair` makes them consistent
+    pub is_consistently_wound: bool,
+    /// Edges that are shared by more than two triangles
+    pub non_manifold_edge_count: usize,
+    /// Vertices where the triangles around them do not form a single fan
+    pub non_manifold_vertex_count: usize,
+    pub boundary_edge_count: usize,
+    pub boundary_loop_count: usize,
+    /// Triangles with repeated vertices or without area, see `Mesh::is_degenerate_triangle`
+    pub degenerate_triangle_count: usize,
+    /// Groups of triangles that are connected through shared vertices
+    pub connected_component_count: usize,
+}
+
+impl MeshStatistics {
+    pub fn new(mesh: &Mesh) -> Self {
+        let bounding_box = BoundingBox::new(&mesh.points);
+        let [bounding_box_min, bounding_box_max] = bounding_box
+            .get_min_max()
+            .map(|corner| mesh.origin.add_local_rounded(&corner));
+
+        let mut surface_area = 0.0;
+        let mut volume = 0.0;
+        let mut degenerate_triangle_count = 0;
+        for triangle in 0..mesh.triangle_count() {
+            let [p1, p2, p3] = mesh.triangle_points(triangle);
+            let cross = cross_product(p2.relative_to(p1), p3.relative_to(p1));
+            let area = dot_product(&cross, &cross).sqrt() / 2.0;
+            if mesh.is_degenerate_triangle(triangle) {
+                degenerate_triangle_count += 1;
+            }
+            surface_area += area;
+            // signed volume of the tetrahedron between the triangle and the origin
+            volume += dot_product(p1, &cross_product(p2.clone(), p3.clone())) / 6.0;
+        }
+
+        let half_edges = HalfEdgeMesh::new(mesh);
+        let edges = half_edges.edges();
+        let non_manifold_edge_count = edges.iter().filter(|edge| !edge.is_manifold()).count();
+        let boundary_edge_count = edges.iter().filter(|edge| edge.is_boundary()).count();
+        let non_manifold_vertex_count = (0..mesh.points.len())
+            .filter(|vertex| !half_edges.is_manifold_vertex(*vertex))
+            .count();
+        let is_watertight =
+            boundary_edge_count == 0 && non_manifold_edge_count == 0 && !mesh.triangles.is_empty();
+        let is_consistently_wound = half_edges.is_consistently_wound();
+
+        MeshStatistics {
+            vertex_count: mesh.points.len(),
+            triangle_count: mesh.triangle_count(),
+            normal_count: mesh.normals.len(),
+            uv_count: mesh.uvs.len(),
+            group_count: mesh.groups.len(),
+            material_count: mesh.materials.len(),
+            bounding_box_min,
+            bounding_box_max,
+            surface_area,
+            volume,
+            is_watertight,
+            is_consistently_wound,
+            non_manifold_edge_count,
+            non_manifold_vertex_count,
+            boundary_edge_count,
+            boundary_loop_count: half_edges.boundary_loops().len(),
+            degenerate_triangle_count,
+            connected_component_count: half_edges.connected_components().len(),
+        }
+    }
+
+    /// The volume can only be measured if the mesh is closed and all triangles face the same side
+    pub fn has_volume(&self) -> bool {
+        self.is_watertight && self.is_consistently_wound
+    }
+
+    pub fn dimensions(&self) -> Point3 {
+        self.bounding_box_min
+            .local_offset_to(&self.bounding_box_max)
+    }
+
+    pub fn to_text(&self) -> String {
+        let volume_text = if !self.is_watertight {
+            String::from("unknown (mesh is not watertight)")
+        } else if !self.is_consistently_wound {
+            String::from("unknown (winding is not consistent)")
+        } else {
+            self.volume.to_string()
+        };
+        [
+            format!("vertices: {}", self.vertex_count),
+            format!("triangles: {}", self.triangle_count),
+            format!("normals: {}", self.normal_count),
+            format!("uvs: {}", self.uv_count),
+            format!("groups: {}", self.group_count),
+            format!("materials: {}", self.material_count),
+            format!(
+                "bounding box min: {}",
+                point_to_text(self.bounding_box_min.to_array())
+            ),
+            format!(
+                "bounding box max: {}",
+                point_to_text(self.bounding_box_max.to_array())
+            ),
+            format!(
+                "dimensions: {}",
+                point_to_text(self.dimensions().to_array())
+            ),
+            format!("surface area: {}", self.surface_area),
+            format!("volume: {volume_text}"),
+            format!("watertight: {}", self.is_watertight),
+            format!("consistent winding: {}", self.is_consistently_wound),
+            format!("non-manifold edges: {}", self.non_manifold_edge_count),
+            format!("non-manifold vertices: {}", self.non_manifold_vertex_count),
+            format!("boundary edges: {}", self.boundary_edge_count),
+            format!("boundary loops: {}", self.boundary_loop_count),
+            format!("degenerate triangles: {}", self.degenerate_triangle_count),
+            format!("connected components: {}", self.connected_component_count),
+        ]
+        .join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        let volume = if self.has_volume() {
+            number_to_json(self.volume)
+        } else {
+            String::from("null")
+        };
+        let fields = [
+            ("vertex_count", self.vertex_count.to_string()),
+            ("triangle_count", self.triangle_count.to_string()),
+            ("normal_count", self.normal_count.to_string()),
+            ("uv_count", self.uv_count.to_string()),
+            ("group_count", self.group_count.to_string()),
+            ("material_count", self.material_count.to_string()),
+            (
+                "bounding_box_min",
+                point_to_json(self.bounding_box_min.to_array()),
+            ),
+            (
+                "bounding_box_max",
+                point_to_json(self.bounding_box_max.to_array()),
+            ),
+            ("dimensions", point_to_json(self.dimensions().to_array())),
+            ("surface_area", number_to_json(self.surface_area)),
+            ("volume", volume),
+            ("is_watertight", self.is_watertight.to_string()),
+            (
+                "is_consistently_wound",
+                self.is_consistently_wound.to_string(),
+            ),
+            (
+                "non_manifold_edge_count",
+                self.non_manifold_edge_count.to_string(),
+            ),
+            (
+                "non_manifold_vertex_count",
+                self.non_manifold_vertex_count.to_string(),
+            ),
+            ("boundary_edge_count", self.boundary_edge_count.to_string()),
+            ("boundary_loop_count", self.boundary_loop_count.to_string()),
+            (
+                "degenerate_triangle_count",
+                self.degenerate_triangle_count.to_string(),
+            ),
+            (
+                "connected_component_count",
+                self.connected_component_count.to_string(),
+            ),
+        ];
+        fields_to_json(&fields)
+    }
+}
+
+/// A JSON object with one field per line, the values have to be valid JSON already
+pub(crate) fn fields_to_json(fields: &[(&str, String)]) -> String {
+    let lines: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("  \"{key}\": {value}"))
+        .collect();
+    format!("{{\n{}\n}}", lines.join(",\n"))
+}
+
+fn point_to_text<T: Display>([x, y, z]: [T; 3]) -> String {
+    format!("{x} {y} {z}")
+}
+
+fn point_to_json<T: Into<f64> + Display + Copy>(point: [T; 3]) -> String {
+    let [x, y, z] = point.map(number_to_json);
+    format!("[{x}, {y}, {z}]")
+}
+
+/// JSON has no representation for NaN or infinity
+pub(crate) fn number_to_json<T: Into<f64> + Display + Copy>(number: T) -> String {
+    if number.into().is_finite() {
+        number.to_string()
+    } else {
+        String::from("null")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MeshStatistics;
+    use crate::general::positions_3d::Point as Point3;
+    use crate::mesh_processing::test_meshes::cube;
+
+    #[test]
+    fn test_closed_cube() {
+        let statistics = MeshStatistics::new(&cube());
+        assert_eq!(statistics.triangle_count, 12);
+        assert!((statistics.surface_area - 6.0).abs() < 1e-5);
+        assert!((statistics.volume - 1.0).abs() < 1e-5);
+        assert!(statistics.is_watertight);
+        assert!(statistics.is_consistently_wound);
+        assert_eq!(statistics.boundary_loop_count, 0);
+        assert_eq!(statistics.non_manifold_vertex_count, 0);
+        assert_eq!(statistics.connected_component_count, 1);
+        assert_eq!(statistics.dimensions(), Point3::from_array([1.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_cube_with_missing_side() {
+        let mut mesh = cube();
+        let keep: Vec<bool> = (0..12).map(|triangle| triangle < 10).collect();
+        mesh.retain_triangles(&keep);
+        let statistics = MeshStatistics::new(&mesh);
+        assert!(!statistics.is_watertight);
+        assert_eq!(statistics.boundary_edge_count, 4);
+        assert_eq!(statistics.boundary_loop_count, 1);
+        assert!(statistics.to_json().contains("\"volume\": null"));
+    }
+
+    #[test]
+    fn test_cube_with_flipped_side_has_no_volume() {
+        let mut mesh = cube();
+        mesh.flip_winding(0);
+        mesh.flip_winding(1);
+        let statistics = MeshStatistics::new(&mesh);
+        assert!(statistics.is_watertight);
+        assert!(!statistics.is_consistently_wound);
+        assert!(statistics.to_json().contains("\"volume\": null"));
+        assert!(statistics
+            .to_text()
+            .contains("volume: unknown (winding is not consistent)"));
+
+        mesh.repair();
+        assert!(MeshStatistics::new(&mesh).has_volume());
+    }
+
+    #[test]
+    fn test_degenerate_triangles_do_not_depend_on_the_units() {
+        // a cube of 0.1 mm in a file in metres
+        let mut mesh = cube();
+        for point in &mut mesh.points {
+            *point = Point3::from_array(point.to_array().map(|coordinate| coordinate * 1e-4));
+        }
+        assert_eq!(MeshStatistics::new(&mesh).degenerate_triangle_count, 0);
+
+        // a sliver along the x axis
+        mesh.points.push(Point3::from_array([2e-4, 1e-15, 0.0]));
+        mesh.push_triangle([0, 1, 8], Point3::new());
+        assert_eq!(MeshStatistics::new(&mesh).degenerate_triangle_count, 1);
+    }
+}
@@ -0,0 +1,511 @@
+use crate::general::positions_3d::{
+    to_mesh_index, FaceData, Mesh, Point as Point3, Triangle as Triangle3,
+};
+use crate::mesh_processing::half_edge::HalfEdgeMesh;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// How `Mesh::subdivide` smooths the mesh.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubdivisionScheme {
+    /// For triangle meshes, splits every triangle into four triangles
+    Loop,
+    /// For quad meshes, splits every polygon into one quad per corner
+    CatmullClark,
+}
+
+impl FromStr for SubdivisionScheme {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "loop" => Ok(SubdivisionScheme::Loop),
+            "catmull-clark" => Ok(SubdivisionScheme::CatmullClark),
+            _ => Err(format!(
+                "'{string}' is not a subdivision scheme, use loop or catmull-clark"
+            )),
+        }
+    }
+}
+
+impl SubdivisionScheme {
+    /// Catmull-Clark if at least half of the polygons are quads, otherwise Loop
+    pub fn for_mesh(mesh: &Mesh) -> Self {
+        let polygons = mesh.polygons();
+        let quad_count = polygons
+            .iter()
+            .filter(|polygon| polygon.vertices.len() == 4)
+            .count();
+        if quad_count * 2 >= polygons.len() && quad_count > 0 {
+            SubdivisionScheme::CatmullClark
+        } else {
+            SubdivisionScheme::Loop
+        }
+    }
+}
+
+/// A face of the mesh before it was split into triangles.
+#[derive(Debug, PartialEq)]
+pub struct Polygon {
+    /// Vertex indices in counterclockwise order, seen from outside
+    pub vertices: Vec<usize>,
+    /// The triangles that the polygon consists of
+    pub triangles: Vec<usize>,
+}
+
+impl Mesh {
+    /// Groups the triangles back into the polygons they were split from, see `FaceData::polygons`.
+    /// Triangles without a polygon, or whose polygon can not be rebuilt, are returned as triangles.
+    pub fn polygons(&self) -> Vec<Polygon> {
+        let mut triangles_per_polygon: Vec<Vec<usize>> = Vec::new();
+        let mut polygon_indices: HashMap<usize, usize> = HashMap::new();
+        for triangle_index in 0..self.triangle_count() {
+            let Some(polygon) = self.polygon(triangle_index) else {
+                triangles_per_polygon.push(vec![triangle_index]);
+                continue;
+            };
+            let polygon_index = *polygon_indices.entry(polygon).or_insert_with(|| {
+                triangles_per_polygon.push(Vec::new());
+                triangles_per_polygon.len() - 1
+            });
+            triangles_per_polygon[polygon_index].push(triangle_index);
+        }
+
+        let mut polygons = Vec::with_capacity(triangles_per_polygon.len());
+        for triangles in triangles_per_polygon {
+            match self.polygon_outline(&triangles) {
+                Some(vertices) => polygons.push(Polygon {
+                    vertices,
+                    triangles,
+                }),
+                None => polygons.extend(triangles.into_iter().map(|triangle_index| Polygon {
+                    vertices: self.triangle_indices(triangle_index).to_vec(),
+                    triangles: vec![triangle_index],
+                })),
+            }
+        }
+        polygons
+    }
+
+    /// The outline of triangles that form a polygon, `None` if they do not form exactly one loop
+    fn polygon_outline(&self, triangles: &[usize]) -> Option<Vec<usize>> {
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for triangle_index in triangles {
+            let [a, b, c] = self.triangle_indices(*triangle_index);
+            for edge in [(a, b), (b, c), (c, a)] {
+                // edges inside the polygon are used by two triangles, in opposite directions
+                match edges.iter().position(|other| *other == (edge.1, edge.0)) {
+                    Some(index) => {
+                        edges.swap_remove(index);
+                    }
+                    None => edges.push(edge),
+                }
+            }
+        }
+
+        let mut outline = vec![edges.first()?.0];
+        for _ in 0..edges.len() - 1 {
+            let current = *outline.last().unwrap();
+            let (_, next) = edges.iter().find(|(start, _)| *start == current)?;
+            outline.push(*next);
+        }
+        let last = *outline.last().unwrap();
+        let is_closed = edges.contains(&(last, outline[0]));
+        let mut sorted_outline = outline.clone();
+        sorted_outline.sort_unstable();
+        sorted_outline.dedup();
+        (is_closed && sorted_outline.len() == outline.len()).then_some(outline)
+    }
+
+    /// Smooths the mesh by subdividing it once.
+    /// Both schemes keep boundaries as curves through the boundary vertices.
+    ///
+    /// Vertex normals and texture coordinates are removed, since they no longer match the vertices.
+    /// Groups and materials are kept.
+    ///
+    /// # Example
+    /// ```
+    /// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+    /// use rust_obj_terminal_viewer::mesh_processing::subdivision::SubdivisionScheme;
+    ///
+    /// let mut mesh = Mesh::new();
+    /// mesh.points = vec![
+    ///     Point3::from_array([0.0, 0.0, 0.0]),
+    ///     Point3::from_array([1.0, 0.0, 0.0]),
+    ///     Point3::from_array([0.0, 1.0, 0.0]),
+    /// ];
+    /// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 0.0, 1.0]));
+    ///
+    /// mesh.subdivide(SubdivisionScheme::Loop);
+    /// assert_eq!(mesh.triangle_count(), 4);
+    /// assert_eq!(mesh.points.len(), 6);
+    /// ```
+    pub fn subdivide(&mut self, scheme: SubdivisionScheme) {
+        match scheme {
+            SubdivisionScheme::Loop => self.subdivide_loop(),
+            SubdivisionScheme::CatmullClark => self.subdivide_catmull_clark(),
+        }
+        self.normals.clear();
+        self.uvs.clear();
+    }
+
+    fn subdivide_loop(&mut self) {
+        let half_edges = HalfEdgeMesh::new(self);
+        let opposite_vertex = |half_edge: usize| half_edges.origin(half_edges.previous(half_edge));
+
+        let mut points: Vec<Point3> = (0..self.points.len())
+            .map(|vertex| {
+                let neighbours = half_edges.vertex_neighbours(vertex);
+                let mut boundary_neighbours = Vec::new();
+                for neighbour in &neighbours {
+                    let edge = half_edges
+                        .find_edge(vertex, *neighbour)
+                        .expect("neighbours share an edge");
+                    if !edge.is_manifold() {
+                        return self.points[vertex].clone();
+                    }
+                    if edge.is_boundary() {
+                        boundary_neighbours.push(*neighbour);
+                    }
+                }
+
+                match (neighbours.len(), boundary_neighbours.len()) {
+                    (0, _) => self.points[vertex].clone(),
+                    (n, 0) => {
+                        let beta = if n == 3 {
+                            3.0 / 16.0
+                        } else {
+                            3.0 / (8.0 * n as f32)
+                        };
+                        let mut weighted = vec![(vertex, 1.0 - n as f32 * beta)];
+                        weighted.extend(neighbours.iter().map(|neighbour| (*neighbour, beta)));
+                        weighted_sum(&self.points, &weighted)
+                    }
+                    // the corners of a triangle without neighbours are kept sharp
+                    (2, 2) => self.points[vertex].clone(),
+                    (_, 2) => weighted_sum(
+                        &self.points,
+                        &[
+                            (vertex, 0.75),
+                            (boundary_neighbours[0], 0.125),
+                            (boundary_neighbours[1], 0.125),
+                        ],
+                    ),
+                    // corners where boundaries meet are kept sharp
+                    _ => self.points[vertex].clone(),
+                }
+            })
+            .collect();
+
+        let edge_points_start = points.len();
+        points.extend(half_edges.edges().iter().map(|edge| {
+            let (a, b) = edge.vertices;
+            match edge.half_edges[..] {
+                [first, second] => weighted_sum(
+                    &self.points,
+                    &[
+                        (a, 0.375),
+                        (b, 0.375),
+                        (opposite_vertex(first), 0.125),
+                        (opposite_vertex(second), 0.125),
+                    ],
+                ),
+                _ => weighted_sum(&self.points, &[(a, 0.5), (b, 0.5)]),
+            }
+        }));
+
+        let mut triangles = Vec::with_capacity(4 * self.triangle_count());
+        for triangle_index in 0..self.triangle_count() {
+            let edge_points: Option<Vec<usize>> = (3 * triangle_index..3 * triangle_index + 3)
+                .map(|half_edge| {
+                    half_edges
+                        .edge_index(half_edge)
+                        .map(|edge_index| edge_points_start + edge_index)
+                })
+                .collect();
+            // degenerate triangles would only create more degenerate triangles
+            let Some(edge_points) = edge_points else {
+                continue;
+            };
+            let [a, b, c] = self.triangle_indices(triangle_index);
+            let [ab, bc, ca] = [edge_points[0], edge_points[1], edge_points[2]];
+            for indices in [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]] {
+                triangles.push(SubdividedTriangle {
+                    source: triangle_index,
+                    indices,
+                    polygon: None,
+                });
+            }
+        }
+
+        self.points = points;
+        self.set_subdivided_triangles(&triangles);
+    }
+
+    fn subdivide_catmull_clark(&mut self) {
+        let polygons = self.polygons();
+        let face_points: Vec<Point3> = polygons
+            .iter()
+            .map(|polygon| {
+                let weight = 1.0 / polygon.vertices.len() as f32;
+                let weighted: Vec<(usize, f32)> = polygon
+                    .vertices
+                    .iter()
+                    .map(|vertex| (*vertex, weight))
+                    .collect();
+                weighted_sum(&self.points, &weighted)
+            })
+            .collect();
+
+        let mut polygons_per_edge: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        let mut polygons_per_vertex = vec![Vec::new(); self.points.len()];
+        for (polygon_index, polygon) in polygons.iter().enumerate() {
+            for (a, b) in polygon_edges(&polygon.vertices) {
+                polygons_per_edge
+                    .entry((a.min(b), a.max(b)))
+                    .or_default()
+                    .push(polygon_index);
+                polygons_per_vertex[a].push(polygon_index);
+            }
+        }
+        // sorted, since the HashMap order would make the vertex order differ between runs
+        let mut edges: Vec<(usize, usize)> = polygons_per_edge.keys().copied().collect();
+        edges.sort_unstable();
+        let mut edges_per_vertex = vec![Vec::new(); self.points.len()];
+        for (a, b) in &edges {
+            edges_per_vertex[*a].push((*a, *b));
+            edges_per_vertex[*b].push((*a, *b));
+        }
+
+        let mut points: Vec<Point3> = (0..self.points.len())
+            .map(|vertex| {
+                let point = &self.points[vertex];
+                let vertex_edges = &edges_per_vertex[vertex];
+                let other_vertex = |(a, b): (usize, usize)| if a == vertex { b } else { a };
+                if vertex_edges
+                    .iter()
+                    .any(|edge| polygons_per_edge[edge].len() > 2)
+                {
+                    return point.clone();
+                }
+                let boundary_neighbours: Vec<usize> = vertex_edges
+                    .iter()
+                    .filter(|edge| polygons_per_edge[*edge].len() == 1)
+                    .map(|edge| other_vertex(*edge))
+                    .collect();
+
+                match (vertex_edges.len(), boundary_neighbours.len()) {
+                    (0, _) => point.clone(),
+                    (valence, 0) => {
+                        let vertex_polygons = &polygons_per_vertex[vertex];
+                        let face_average = vertex_polygons
+                            .iter()
+                            .fold(Point3::new(), |sum, polygon| {
+                                sum.add(&face_points[*polygon])
+                            })
+                            .map(|component| component / vertex_polygons.len() as f32);
+                        let edge_midpoint_average = vertex_edges
+                            .iter()
+                            .fold(Point3::new(), |sum, edge| {
+                                sum.add(&self.points[edge.0]).add(&self.points[edge.1])
+                            })
+                            .map(|component| component / (2 * valence) as f32);
+                        let n = valence as f32;
+                        face_average
+                            .add(&edge_midpoint_average.map(|component| component * 2.0))
+                            .add(&point.map(|component| component * (n - 3.0)))
+                            .map(|component| component / n)
+                    }
+                    // the corners of a polygon without neighbours are kept sharp
+                    (2, 2) => point.clone(),
+                    (_, 2) => weighted_sum(
+                        &self.points,
+                        &[
+                            (vertex, 0.75),
+                            (boundary_neighbours[0], 0.125),
+                            (boundary_neighbours[1], 0.125),
+                        ],
+                    ),
+                    // corners where boundaries meet are kept sharp
+                    _ => point.clone(),
+                }
+            })
+            .collect();
+
+        let mut edge_point_indices = HashMap::with_capacity(edges.len());
+        for (a, b) in edges {
+            let midpoint = weighted_sum(&self.points, &[(a, 0.5), (b, 0.5)]);
+            let edge_point = match polygons_per_edge[&(a, b)][..] {
+                [first, second] => midpoint
+                    .add(
+                        &face_points[first]
+                            .add(&face_points[second])
+                            .map(|c| c * 0.5),
+                    )
+                    .map(|component| component * 0.5),
+                _ => midpoint,
+            };
+            edge_point_indices.insert((a, b), points.len());
+            points.push(edge_point);
+        }
+        let edge_point = |a: usize, b: usize| edge_point_indices[&(a.min(b), a.max(b))];
+
+        let face_points_start = points.len();
+        points.extend(face_points);
+
+        let mut triangles = Vec::new();
+        let mut quad_count = 0;
+        for (polygon_index, polygon) in polygons.iter().enumerate() {
+            let source = polygon.triangles[0];
+            let face_point = face_points_start + polygon_index;
+            let vertices = &polygon.vertices;
+            for corner in 0..vertices.len() {
+                let vertex = vertices[corner];
+                let next = vertices[(corner + 1) % vertices.len()];
+                let previous = vertices[(corner + vertices.len() - 1) % vertices.len()];
+                let quad = [
+                    vertex,
+                    edge_point(vertex, next),
+                    face_point,
+                    edge_point(previous, vertex),
+                ];
+                for indices in [[quad[0], quad[1], quad[2]], [quad[0], quad[2], quad[3]]] {
+                    triangles.push(SubdividedTriangle {
+                        source,
+                        indices,
+                        polygon: Some(quad_count),
+                    });
+                }
+                quad_count += 1;
+            }
+        }
+
+        self.points = points;
+        self.set_subdivided_triangles(&triangles);
+    }
+
+    /// Replaces the triangles and computes their normals from the new points.
+    /// Groups and materials are taken from the source triangles, other face data is removed.
+    fn set_subdivided_triangles(&mut self, triangles: &[SubdividedTriangle]) {
+        let source_values = |values: &Option<Vec<u32>>| {
+            let values = values.as_ref()?;
+            Some(
+                triangles
+                    .iter()
+                    .map(|triangle| values[triangle.source])
+                    .collect(),
+            )
+        };
+        self.face_data = FaceData {
+            groups: source_values(&self.face_data.groups),
+            materials: source_values(&self.face_data.materials),
+            // either all or none of the triangles belong to a polygon
+            polygons: triangles
+                .iter()
+                .map(|triangle| triangle.polygon.map(to_mesh_index))
+                .collect(),
+            ..FaceData::default()
+        };
+        self.triangles = triangles
+            .iter()
+            .map(|triangle| triangle.indices.map(to_mesh_index))
+            .collect();
+        self.face_normals = (0..self.triangle_count())
+            .map(|triangle| Triangle3::get_normal(&self.triangle_points(triangle)))
+            .collect();
+    }
+}
+
+/// A triangle created by subdivision
+struct SubdividedTriangle {
+    /// The triangle that the new triangle was created from
+    source: usize,
+    indices: [usize; 3],
+    polygon: Option<usize>,
+}
+
+/// Each vertex point of a fully subdivided surface depends on the vertices around it.
+/// The weights should sum up to 1.
+fn weighted_sum(points: &[Point3], weights: &[(usize, f32)]) -> Point3 {
+    weights.iter().fold(Point3::new(), |sum, (vertex, weight)| {
+        sum.add(&points[*vertex].map(|component| component * weight))
+    })
+}
+
+fn polygon_edges(vertices: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..vertices.len()).map(|i| (vertices[i], vertices[(i + 1) % vertices.len()]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubdivisionScheme;
+    use crate::general::positions_3d::Point as Point3;
+    use crate::mesh_processing::statistics::MeshStatistics;
+    use crate::mesh_processing::test_meshes::{cube, square};
+
+    #[test]
+    fn test_polygons_are_rebuilt_from_triangles() {
+        let mesh = cube();
+        let polygons = mesh.polygons();
+        assert_eq!(polygons.len(), 6);
+        assert_eq!(polygons[0].vertices, vec![0, 2, 3, 1]);
+        assert_eq!(polygons[0].triangles, vec![0, 1]);
+        assert_eq!(
+            SubdivisionScheme::for_mesh(&mesh),
+            SubdivisionScheme::CatmullClark
+        );
+    }
+
+    #[test]
+    fn test_catmull_clark_cube() {
+        let mut mesh = cube();
+        mesh.subdivide(SubdivisionScheme::CatmullClark);
+        assert_eq!(mesh.points.len(), 8 + 12 + 6);
+        assert_eq!(mesh.polygons().len(), 24);
+        // the corners of a cube move 2/9 towards the center
+        let corner = &mesh.points[0];
+        assert!(corner
+            .to_array()
+            .iter()
+            .all(|c| (c - 2.0 / 9.0).abs() < 1e-6));
+
+        let statistics = MeshStatistics::new(&mesh);
+        assert!(statistics.is_watertight);
+        assert!(statistics.volume > 0.0 && statistics.volume < 1.0);
+    }
+
+    #[test]
+    fn test_loop_subdivision_keeps_closed_mesh_closed() {
+        let mut mesh = cube();
+        mesh.face_data.polygons = None;
+        assert_eq!(SubdivisionScheme::for_mesh(&mesh), SubdivisionScheme::Loop);
+        mesh.subdivide(SubdivisionScheme::Loop);
+        assert_eq!(mesh.triangle_count(), 48);
+        assert_eq!(mesh.points.len(), 8 + 18);
+
+        let statistics = MeshStatistics::new(&mesh);
+        assert!(statistics.is_watertight);
+        assert!(statistics.volume > 0.0 && statistics.volume < 1.0);
+    }
+
+    #[test]
+    fn test_flat_surface_stays_flat() {
+        for scheme in [SubdivisionScheme::Loop, SubdivisionScheme::CatmullClark] {
+            let mut mesh = square();
+            mesh.subdivide(scheme);
+            mesh.subdivide(scheme);
+            assert!(mesh.points.iter().all(|point| point.z == 0.0
+                && (0.0..=1.0).contains(&point.x)
+                && (0.0..=1.0).contains(&point.y)));
+        }
+    }
+
+    #[test]
+    fn test_catmull_clark_keeps_corners_of_single_quad() {
+        let mut mesh = square();
+        mesh.subdivide(SubdivisionScheme::CatmullClark);
+        assert_eq!(mesh.points[2], Point3::from_array([1.0, 1.0, 0.0]));
+        // the center of the quad
+        assert_eq!(mesh.points[8], Point3::from_array([0.5, 0.5, 0.0]));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::general::positions_3d::{Mesh, Point as Point3};
+
+/// A unit cube made of quads with outwards facing triangles, like the parser creates them
+pub fn cube() -> Mesh {
+    let mut mesh = Mesh::new();
+    for i in 0..8 {
+        let bit = |n: usize| ((i >> n) & 1) as f32;
+        mesh.points
+            .push(Point3::from_array([bit(0), bit(1), bit(2)]));
+    }
+    let quads = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ];
+    for (polygon, [a, b, c, d]) in quads.into_iter().enumerate() {
+        for indices in [[a, b, c], [a, c, d]] {
+            let triangle = mesh.push_triangle(indices, Point3::new());
+            mesh.set_polygon(triangle, polygon);
+        }
+    }
+    mesh
+}
+
+/// A unit square in the XY plane facing up, made of one quad
+pub fn square() -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.points = vec![
+        Point3::from_array([0.0, 0.0, 0.0]),
+        Point3::from_array([1.0, 0.0, 0.0]),
+        Point3::from_array([1.0, 1.0, 0.0]),
+        Point3::from_array([0.0, 1.0, 0.0]),
+    ];
+    let normal = Point3::from_array([0.0, 0.0, 1.0]);
+    for indices in [[0, 1, 2], [0, 2, 3]] {
+        let triangle = mesh.push_triangle(indices, normal.clone());
+        mesh.set_polygon(triangle, 0);
+    }
+    mesh
+}
+
+/// A tetrahedron with outwards facing triangles
+pub fn tetrahedron() -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.points = vec![
+        Point3::from_array([0.0, 0.0, 0.0]),
+        Point3::from_array([1.0, 0.0, 0.0]),
+        Point3::from_array([0.0, 1.0, 0.0]),
+        Point3::from_array([0.0, 0.0, 1.0]),
+    ];
+    for triangle in [[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]] {
+        mesh.push_triangle(triangle, Point3::new());
+    }
+    mesh
+}
+
+/// A flat grid of `size` × `size` squares in the XY plane facing up, two triangles each
+pub fn grid(size: usize) -> Mesh {
+    let mut mesh = Mesh::new();
+    for y in 0..=size {
+        for x in 0..=size {
+            mesh.points
+                .push(Point3::from_array([x as f32, y as f32, 0.0]));
+        }
+    }
+    let normal = Point3::from_array([0.0, 0.0, 1.0]);
+    let index = |x: usize, y: usize| y * (size + 1) + x;
+    for y in 0..size {
+        for x in 0..size {
+            mesh.push_triangle(
+                [index(x, y), index(x + 1, y), index(x + 1, y + 1)],
+                normal.clone(),
+            );
+            mesh.push_triangle(
+                [index(x, y), index(x + 1, y + 1), index(x, y + 1)],
+                normal.clone(),
+            );
+        }
+    }
+    mesh
+}
@@ -0,0 +1,149 @@
+use crate::general::positions_3d::{Degrees, Mesh, Point as Point3};
+use crate::renderer::pipeline::transformation::{
+    get_multiplied_directions_with_matrix, get_multiplied_points_with_matrix, rotation_matrix_axis,
+    scale_matrix, translation_matrix, Matrix4x4, MatrixTrait,
+};
+
+impl Mesh {
+    /// Transforms all points of the mesh with the matrix.
+    ///
+    /// Normals are transformed with the inverse transpose of the matrix,
+    /// so that they stay perpendicular to the surface after non-uniform scaling.
+    /// If the matrix mirrors the mesh, the winding order is reversed to keep the triangles facing outwards.
+    /// The translation is applied to the points, while `Mesh::origin` is only rotated and scaled,
+    /// which together transforms the global positions of the points.
+    /// Rotations of meshes that are far away from the origin are limited by the `f32` precision of the matrix.
+    /// Returns an error if the matrix is not invertible, since the surface would collapse.
+    pub fn transform(&mut self, matrix: &Matrix4x4) -> Result<(), String> {
+        let normal_matrix = matrix
+            .inverse()
+            .ok_or("the transformation matrix is not invertible")?
+            .transpose();
+
+        self.points = get_multiplied_points_with_matrix(&self.points, matrix);
+        self.origin = self.origin.map_linear(|direction| {
+            get_multiplied_directions_with_matrix(std::slice::from_ref(direction), matrix).remove(0)
+        });
+        self.normals = get_multiplied_directions_with_matrix(&self.normals, &normal_matrix)
+            .iter()
+            .map(Point3::normalized)
+            .collect();
+
+        self.face_normals =
+            get_multiplied_directions_with_matrix(&self.face_normals, &normal_matrix)
+                .iter()
+                .map(Point3::normalized)
+                .collect();
+        if determinant3x3(matrix) < 0.0 {
+            for triangle in 0..self.triangle_count() {
+                self.flip_winding(triangle);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn translate(&mut self, offset: &Point3) {
+        self.transform(&translation_matrix(offset.x, offset.y, offset.z))
+            .expect("translation matrices are invertible");
+    }
+
+    /// Rotates around an axis through the origin, counterclockwise when looking against the axis direction.
+    pub fn rotate(&mut self, axis: &Point3, angle: Degrees) {
+        self.transform(&rotation_matrix_axis(axis, angle.0.to_radians()))
+            .expect("rotation matrices are invertible");
+    }
+
+    /// Scales each axis separately. Negative factors mirror the mesh.
+    pub fn scale(&mut self, factors: &Point3) -> Result<(), String> {
+        self.transform(&scale_matrix(factors.x, factors.y, factors.z))
+    }
+}
+
+/// Determinant of the upper left 3x3 part, negative if the matrix mirrors
+fn determinant3x3(m: &Matrix4x4) -> f32 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::general::positions_3d::{
+        Degrees, GlobalPoint, Mesh, Point as Point3, Triangle as Triangle3,
+    };
+
+    /// A triangle that is tilted 45 degrees around the z axis
+    fn tilted_triangle() -> Mesh {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.0, 0.0, 0.0]),
+            Point3::from_array([1.0, 1.0, 0.0]),
+            Point3::from_array([0.0, 0.0, -1.0]),
+        ];
+        let normal = Point3::from_array([-1.0, 1.0, 0.0]).normalized();
+        mesh.normals = vec![normal.clone()];
+        mesh.push_triangle([0, 1, 2], normal);
+        mesh
+    }
+
+    fn assert_close(a: &Point3, b: &Point3) {
+        let difference = a.relative_to(b);
+        assert!(
+            difference.to_array().iter().all(|c| c.abs() < 1e-5),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    fn computed_normal(mesh: &Mesh) -> Point3 {
+        Triangle3::get_normal(&mesh.triangle_points(0))
+    }
+
+    #[test]
+    fn test_non_uniform_scale_keeps_normals_perpendicular() {
+        let mut mesh = tilted_triangle();
+        mesh.scale(&Point3::from_array([2.0, 1.0, 1.0])).unwrap();
+        let expected = Point3::from_array([-1.0, 2.0, 0.0]).normalized();
+        assert_close(&mesh.face_normals[0], &expected);
+        assert_close(&mesh.normals[0], &expected);
+        assert_close(&computed_normal(&mesh), &expected);
+    }
+
+    #[test]
+    fn test_mirroring_keeps_winding_consistent() {
+        let mut mesh = tilted_triangle();
+        mesh.scale(&Point3::from_array([-1.0, 1.0, 1.0])).unwrap();
+        assert_close(&computed_normal(&mesh), &mesh.face_normals[0]);
+    }
+
+    #[test]
+    fn test_rotate_and_translate() {
+        let mut mesh = tilted_triangle();
+        mesh.rotate(&Point3::from_array([0.0, 0.0, 1.0]), Degrees(45.0));
+        mesh.translate(&Point3::from_array([0.0, 0.0, 5.0]));
+        assert_close(
+            &mesh.points[1],
+            &Point3::from_array([0.0, 2.0_f32.sqrt(), 5.0]),
+        );
+        assert_close(&mesh.face_normals[0], &Point3::from_array([-1.0, 0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_scaling_to_zero_fails() {
+        let mut mesh = tilted_triangle();
+        assert!(mesh.scale(&Point3::from_array([1.0, 0.0, 1.0])).is_err());
+    }
+
+    #[test]
+    fn test_transforming_rebased_mesh_keeps_global_positions() {
+        let mut mesh = tilted_triangle();
+        mesh.origin = GlobalPoint::from_array([600_000.0, 0.0, 5_000_000.0]);
+        mesh.scale(&Point3::from_array([2.0, 1.0, -1.0])).unwrap();
+        mesh.translate(&Point3::from_array([0.01, 0.0, 0.0]));
+
+        let global = mesh.to_global(&mesh.points[1]);
+        let expected = [1_200_002.01, 1.0, -5_000_000.0];
+        for (component, expected) in global.to_array().iter().zip(expected) {
+            assert!((component - expected).abs() < 1e-6, "{global:?}");
+        }
+    }
+}
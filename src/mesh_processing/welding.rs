@@ -0,0 +1,180 @@
+use crate::general::positions_3d::{distance, to_mesh_index, Mesh, Point as Point3};
+use std::collections::HashMap;
+
+type CellKey = (i64, i64, i64);
+
+impl Mesh {
+    /// Merges vertices that are at most `tolerance` apart and remaps the triangle indices.
+    ///
+    /// The first vertex found in a cluster is kept, the others are removed.
+    /// Triangles that collapse because two of their vertices were merged are removed.
+    /// Vertices with infinite or NaN coordinates are never merged.
+    /// Returns the amount of vertices that were merged away.
+    ///
+    /// # Example
+    /// ```
+    /// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+    ///
+    /// let mut mesh = Mesh::new();
+    /// mesh.points = vec![
+    ///     Point3::from_array([0.0, 0.0, 0.0]),
+    ///     Point3::from_array([1.0, 0.0, 0.0]),
+    ///     Point3::from_array([0.0, 1.0, 0.0]),
+    ///     Point3::from_array([1.0, 0.0, 0.0]),
+    /// ];
+    /// mesh.push_triangle([0, 3, 2], Point3::from_array([0.0, 0.0, 1.0]));
+    /// assert_eq!(mesh.weld_vertices(0.001), 1);
+    /// assert_eq!(mesh.points.len(), 3);
+    /// assert_eq!(mesh.triangles[0], [0, 1, 2]);
+    /// ```
+    pub fn weld_vertices(&mut self, tolerance: f32) -> usize {
+        let cell_size = tolerance.max(f32::EPSILON);
+        let mut cells: HashMap<CellKey, Vec<usize>> = HashMap::new();
+        let mut welded_points: Vec<Point3> = Vec::new();
+        let mut index_map: Vec<u32> = Vec::with_capacity(self.points.len());
+
+        for point in &self.points {
+            let is_finite = point
+                .to_array()
+                .iter()
+                .all(|component| component.is_finite());
+            if !is_finite {
+                index_map.push(to_mesh_index(welded_points.len()));
+                welded_points.push(point.clone());
+                continue;
+            }
+            let key = cell_key(point, cell_size);
+            let existing_index = neighbouring_cell_keys(key)
+                .iter()
+                .filter_map(|neighbour_key| cells.get(neighbour_key))
+                .flatten()
+                .find(|welded_index| distance(&welded_points[**welded_index], point) <= tolerance)
+                .copied();
+
+            let new_index = match existing_index {
+                Some(index) => index,
+                None => {
+                    welded_points.push(point.clone());
+                    let index = welded_points.len() - 1;
+                    cells.entry(key).or_default().push(index);
+                    index
+                }
+            };
+            index_map.push(to_mesh_index(new_index));
+        }
+
+        for triangle in &mut self.triangles {
+            *triangle = triangle.map(|index| index_map[index as usize]);
+        }
+        let keep: Vec<bool> = self
+            .triangles
+            .iter()
+            .map(|[p1, p2, p3]| p1 != p2 && p2 != p3 && p3 != p1)
+            .collect();
+        self.retain_triangles(&keep);
+
+        let merged_vertices = self.points.len() - welded_points.len();
+        self.points = welded_points;
+        merged_vertices
+    }
+}
+
+fn cell_key(point: &Point3, cell_size: f32) -> CellKey {
+    let to_cell = |component: f32| (component / cell_size).floor() as i64;
+    (to_cell(point.x), to_cell(point.y), to_cell(point.z))
+}
+
+/// Returns the key of the cell itself and of its 26 neighbours.
+/// Since the cell size is at least the tolerance,
+/// all points within the tolerance are inside one of these cells.
+fn neighbouring_cell_keys(key: CellKey) -> Vec<CellKey> {
+    let mut keys = Vec::with_capacity(27);
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                // points far from the origin are all in the cells at the limits of i64
+                keys.push((
+                    key.0.saturating_add(dx),
+                    key.1.saturating_add(dy),
+                    key.2.saturating_add(dz),
+                ));
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::general::positions_3d::{Mesh, Point as Point3};
+
+    fn push_triangle(mesh: &mut Mesh, indices: [usize; 3]) {
+        mesh.push_triangle(indices, Point3::from_array([0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_weld_separate_triangles_sharing_an_edge() {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.0, 0.0, 0.0]),
+            Point3::from_array([1.0, 0.0, 0.0]),
+            Point3::from_array([0.0, 1.0, 0.0]),
+            Point3::from_array([1.0, 0.0001, 0.0]),
+            Point3::from_array([1.0, 1.0, 0.0]),
+            Point3::from_array([0.0001, 1.0, 0.0]),
+        ];
+        push_triangle(&mut mesh, [0, 1, 2]);
+        push_triangle(&mut mesh, [3, 4, 5]);
+
+        assert_eq!(mesh.weld_vertices(0.001), 2);
+        assert_eq!(mesh.points.len(), 4);
+        assert_eq!(mesh.triangles[1], [1, 3, 2]);
+    }
+
+    #[test]
+    fn test_weld_removes_collapsed_triangles() {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.0, 0.0, 0.0]),
+            Point3::from_array([0.01, 0.0, 0.0]),
+            Point3::from_array([0.0, 1.0, 0.0]),
+        ];
+        push_triangle(&mut mesh, [0, 1, 2]);
+
+        assert_eq!(mesh.weld_vertices(0.1), 1);
+        assert!(mesh.triangles.is_empty());
+        assert!(mesh.face_normals.is_empty());
+    }
+
+    #[test]
+    fn test_weld_with_zero_tolerance_only_merges_identical_points() {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([0.5, 0.5, 0.5]),
+            Point3::from_array([0.5, 0.5, 0.5]),
+            Point3::from_array([0.5, 0.5, 0.50001]),
+        ];
+
+        assert_eq!(mesh.weld_vertices(0.0), 1);
+        assert_eq!(mesh.points.len(), 2);
+    }
+
+    #[test]
+    fn test_weld_keeps_non_finite_points() {
+        let mut mesh = Mesh::new();
+        mesh.points = vec![
+            Point3::from_array([f32::INFINITY, 0.0, 0.0]),
+            Point3::from_array([f32::INFINITY, 0.0, 0.0]),
+            Point3::from_array([f32::NAN, 0.0, 0.0]),
+            Point3::from_array([0.0, f32::NEG_INFINITY, 0.0]),
+            Point3::from_array([1e30, 0.0, 0.0]),
+            Point3::from_array([1e30, 0.0, 0.0]),
+        ];
+        push_triangle(&mut mesh, [0, 1, 2]);
+
+        // only the large but finite points are merged
+        assert_eq!(mesh.weld_vertices(0.001), 1);
+        assert_eq!(mesh.points.len(), 5);
+        assert_eq!(mesh.triangles[0], [0, 1, 2]);
+    }
+}
@@ -4,28 +4,37 @@ use crossterm::event::{
     Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
 
-/// Rotates a camera around a point (0,0,0) based on input events.
+/// Rotates a camera around a pivot point based on input events.
+/// The pivot point is (0,0,0) unless changed with `set_pivot`.
 /// Hold left mouse button (or the middle mouse button) and drag to spin the camera.
 /// You can also press the c key and then move the mouse to spin the camera.
 pub struct CameraInputHelper {
     drag_key_is_down: bool,
+    drag_button_is_down: bool,
     mouse_column: u16,
     mouse_row: u16,
     drag_rotation: DragRotation,
     camera_distance: f32,
+    pivot: Point3,
 }
 
 impl CameraInputHelper {
     pub fn new(terminal_height: u16, terminal_width: u16, camera_distance: f32) -> Self {
         CameraInputHelper {
             drag_key_is_down: false,
+            drag_button_is_down: false,
             mouse_column: 0,
             mouse_row: 0,
             drag_rotation: DragRotation::new(terminal_height, terminal_width, 2.0, 0.5),
             camera_distance,
+            pivot: Point3::new(),
         }
     }
 
+    pub fn set_pivot(&mut self, pivot: Point3) {
+        self.pivot = pivot;
+    }
+
     pub fn process_input_events(&mut self, events: Vec<Event>) {
         for event in events {
             match event {
@@ -41,7 +50,12 @@ impl CameraInputHelper {
 
     pub fn apply_to_camera(&self, camera: &mut Camera) {
         self.drag_rotation
-            .apply_to_camera(camera, self.camera_distance);
+            .apply_to_camera(camera, &self.pivot, self.camera_distance);
+    }
+
+    /// Returns true while the user is spinning the camera
+    pub fn is_rotating(&self) -> bool {
+        self.drag_key_is_down || self.drag_button_is_down
     }
 
     fn process_mouse_event(&mut self, mouse_event: MouseEvent) {
@@ -60,9 +74,13 @@ impl CameraInputHelper {
                 }
             }
             MouseEventKind::Down(MouseButton::Middle) | MouseEventKind::Down(MouseButton::Left) => {
+                self.drag_button_is_down = true;
                 self.drag_rotation
                     .handle_drag_start(mouse_event.column, mouse_event.row)
             }
+            MouseEventKind::Up(MouseButton::Middle) | MouseEventKind::Up(MouseButton::Left) => {
+                self.drag_button_is_down = false;
+            }
             _ => (),
         }
     }
@@ -168,13 +186,13 @@ impl DragRotation {
     }
 
     /// Uses the right hand coordinate system.
-    fn apply_to_camera(&self, camera: &mut Camera, distance: f32) {
+    fn apply_to_camera(&self, camera: &mut Camera, pivot: &Point3, distance: f32) {
         // Assumes that camera is pointing towards -Z when rotation is 0.
         let (rotation_around_x, rotation_around_y) = self.get_rotation_xy();
         let x = rotation_around_y.sin() * rotation_around_x.cos() * distance;
         let y = rotation_around_x.sin() * distance;
         let z = rotation_around_y.cos() * rotation_around_x.cos() * distance;
-        camera.position = Point3 { x, y, z };
+        camera.position = pivot.add(&Point3 { x, y, z });
         let (rotation_x, rotation_y) = self.get_rotation_xy();
         camera.rotation_around_x = -rotation_x;
         camera.rotation_around_y = rotation_y;
@@ -34,3 +34,12 @@ pub fn should_exit(event: &Event) -> bool {
 
     return false;
 }
+
+pub fn toggles_shadows(event: &Event) -> bool {
+    match event {
+        Event::Key(key_event) => {
+            key_event.modifiers == KeyModifiers::NONE && key_event.code == KeyCode::Char('h')
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,99 @@
+use crate::general::positions_3d::{Mesh, Point as Point3};
+use crate::mesh_processing::explode::ExplodedView;
+use crossterm::event::{Event, KeyCode};
+use std::time::Instant;
+
+/// Change of the explosion factor for each key press
+const FACTOR_STEP: f32 = 0.25;
+const MAX_FACTOR: f32 = 4.0;
+/// How fast the factor moves to the chosen one, per second
+const ANIMATION_SPEED: f32 = 1.0;
+
+/// Moves the connected components of the mesh apart based on key presses.
+/// Press `e` to move them further apart and `E` to move them back together.
+/// The components move smoothly to their new positions.
+pub struct ExplodeInputHelper {
+    /// Built on the first key press, since finding the components takes a while for large meshes
+    exploded_view: Option<ExplodedView>,
+    factor: f32,
+    target_factor: f32,
+    last_update: Instant,
+    has_changed: bool,
+}
+
+impl ExplodeInputHelper {
+    pub fn new() -> Self {
+        ExplodeInputHelper {
+            exploded_view: None,
+            factor: 0.0,
+            target_factor: 0.0,
+            last_update: Instant::now(),
+            has_changed: false,
+        }
+    }
+
+    /// Has to be called when the mesh changes, for example when it is subdivided
+    pub fn mesh_changed(&mut self) {
+        self.exploded_view = None;
+        self.has_changed = true;
+    }
+
+    /// Also advances the animation, so it has to be called every frame
+    pub fn process_input_events(&mut self, events: &[Event]) {
+        for event in events {
+            let Event::Key(key_event) = event else {
+                continue;
+            };
+            self.target_factor = match key_event.code {
+                KeyCode::Char('e') => (self.target_factor + FACTOR_STEP).min(MAX_FACTOR),
+                KeyCode::Char('E') => (self.target_factor - FACTOR_STEP).max(0.0),
+                _ => continue,
+            };
+        }
+
+        let now = Instant::now();
+        let max_change = ANIMATION_SPEED * now.duration_since(self.last_update).as_secs_f32();
+        self.last_update = now;
+        if self.factor != self.target_factor {
+            let change = (self.target_factor - self.factor).clamp(-max_change, max_change);
+            self.factor += change;
+            self.has_changed = true;
+        }
+    }
+
+    /// Returns true once after the components were moved
+    pub fn take_has_changed(&mut self) -> bool {
+        std::mem::take(&mut self.has_changed)
+    }
+
+    /// True while any component is moved away from its original position
+    pub fn is_exploded(&self) -> bool {
+        self.factor > 0.0
+    }
+
+    /// The points of `mesh` with the components moved apart
+    pub fn exploded_points(&mut self, mesh: &Mesh) -> Vec<Point3> {
+        if !self.is_exploded() {
+            return mesh.points.clone();
+        }
+        self.exploded_view
+            .get_or_insert_with(|| ExplodedView::new(mesh))
+            .exploded_points(&mesh.points, self.factor)
+    }
+
+    /// A short description like "exploded view 0.5 (12 parts)", or `None` while the mesh is not exploded
+    pub fn description(&self) -> Option<String> {
+        let exploded_view = self.exploded_view.as_ref().filter(|_| self.is_exploded())?;
+        Some(format!(
+            "exploded view {} ({} parts)",
+            self.target_factor,
+            exploded_view.component_count()
+        ))
+    }
+}
+
+impl Default for ExplodeInputHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
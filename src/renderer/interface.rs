@@ -1,10 +1,17 @@
 use super::events::*;
+use super::pipeline::ambient_occlusion::AmbientOcclusion;
+use super::pipeline::culling::GroupCulling;
+use super::pipeline::fragment_shader::{BlinnPhongShader, Lighting, Shader};
+use super::pipeline::light::Light;
+use super::pipeline::shadow::ShadowMap;
 use super::pipeline::terminal_output::{
-    add_info_line_to_char_buffer, draw_char_buffer, image_buffer_to_char_buffer,
+    add_info_line_to_char_buffer, downsample_image_buffer, draw_char_buffer,
+    image_buffer_to_char_buffer,
 };
-use super::render::render_mesh;
-use crate::general::positions_3d::Mesh;
+use super::render::{render_mesh, render_overlay_lines, Shading, Surface};
 use crate::general::positions_3d::Point as Point3;
+use crate::general::positions_3d::{Degrees, Mesh};
+use crate::mesh_processing::normals::NormalWeighting;
 use crossterm::{
     cursor,
     event::Event,
@@ -19,27 +26,70 @@ use std::{
 
 pub use super::pipeline::transformation::Camera;
 
+/// Not part of the brightness characters, so that overlay lines stand out from the model
+const OVERLAY_LINE_CHAR: u8 = b'=';
+const DEFAULT_CREASE_ANGLE: Degrees = Degrees(45.0);
+/// Texels of the shadow maps per sample of the image, along each axis
+const SHADOW_MAP_RESOLUTION: usize = 2;
+
 // TODO should have separate camera struct, with both fov and view_point
 pub struct Renderer {
     width: u16,
     height: u16,
     pub camera: Camera,
     chars: Vec<u8>,
+    /// Change it with `set_mesh` or `set_mesh_points`, so that the shadow maps are made again
     pub mesh: Mesh,
+    /// Simplified version of `mesh`, rendered instead of it while `use_lod_mesh` is true.
+    /// Change it with `set_lod_mesh`, so that the shadow maps are made again
+    pub lod_mesh: Option<Mesh>,
+    pub use_lod_mesh: bool,
+    /// Lines in world space that are drawn on top of the mesh
+    pub overlay_lines: Vec<[Point3; 2]>,
     pub frame_time: Duration,
     pub char_buffer: Buffer<u8>,
     pub prev_char_buffer: Buffer<u8>,
+    /// Rendered at `supersampling` times the terminal size in both directions
     image_buffer: Buffer<f32>,
     depth_buffer: Buffer<f32>,
+    /// Samples per character along each axis, averaged so that thin parts don't flicker while rotating.
+    /// For example 1 (no anti-aliasing, the default), 2 or 4. Values below 1 are treated as 1
+    pub supersampling: u8,
+    /// `image_buffer` at the terminal size, only used with supersampling
+    downsampled_image_buffer: Buffer<f32>,
+    /// Reused every frame for the vertices of the mesh in homogeneous clip space
+    transformed_points: Vec<[f32; 4]>,
+    /// Made for `mesh` and `lod_mesh` when they are rendered, and made again if their amount of points or triangles changes
+    mesh_culling: Option<GroupCulling>,
+    lod_mesh_culling: Option<GroupCulling>,
     pub info_text: Option<String>,
     pub info_line: String,
-    pub light_direction: Point3,
+    /// Passed to the shader as `Fragment::color` if it has one value for every point of the mesh,
+    /// for example to show deviations with `VertexColorShader`
+    pub vertex_colors: Option<Vec<f32>>,
+    /// Decides the brightness of every pixel of the mesh.
+    /// `BlinnPhongShader` by default, which needs the materials of the mesh to show them
+    pub shader: Box<dyn Shader>,
+    /// A single directional light from the top left by default
+    pub lights: Vec<Light>,
     pub ambient_light: f32,
+    /// Directional lights cast shadows, press h to toggle them
+    pub shadows: bool,
+    /// Kept between frames, because making them takes about as long as rendering the mesh
+    shadow_maps: Option<ShadowMaps>,
+    /// Darkens crevices and the areas where parts touch, off by default
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    /// Vertex normals are generated for meshes without them, unless the shading is flat
+    pub shading: Shading,
+    /// Edges sharper than this stay hard when vertex normals are generated for smooth shading, 45 degrees by default
+    pub crease_angle: Degrees,
+    /// The crease angle the vertex normals of `mesh` and `lod_mesh` were generated with, `None` if they were not generated
+    mesh_normals_crease_angle: Option<Degrees>,
+    lod_mesh_normals_crease_angle: Option<Degrees>,
     pub near: f32,
     pub far: f32,
 }
 
-// TODO #anti_aliasing: add parameter for antialiasing sampling (aa: u8), example values: 1 (normal), 2, 4, 8, ...
 // TODO take a config struct?
 impl Renderer {
     pub fn new(
@@ -70,21 +120,32 @@ impl Renderer {
             camera,
             chars: brightness_string.as_bytes().to_vec(),
             mesh: Mesh::new(),
+            lod_mesh: None,
+            use_lod_mesh: false,
+            overlay_lines: Vec::new(),
             frame_time: Duration::from_secs_f32(1.0 / fps),
             char_buffer: empty_char_buffer.clone(),
             prev_char_buffer: empty_char_buffer.clone(),
             image_buffer: Buffer::new(width as usize, height as usize, 0.0),
             depth_buffer: Buffer::new(width as usize, height as usize, f32::MAX),
+            supersampling: 1,
+            downsampled_image_buffer: Buffer::new(width as usize, height as usize, 0.0),
+            transformed_points: Vec::new(),
+            mesh_culling: None,
+            lod_mesh_culling: None,
             info_text: None,
             info_line: "".to_string(),
-            // TODO make parameter of Renderer::new()
-            light_direction: Point3 {
-                x: -0.3,
-                y: -0.5,
-                z: -0.5,
-            }
-            .normalized(),
+            lights: vec![Light::directional(Point3::from_array([-0.3, -0.5, -0.5]))],
+            vertex_colors: None,
+            shader: Box::new(BlinnPhongShader::default()),
             ambient_light: 0.05,
+            shadows: false,
+            shadow_maps: None,
+            ambient_occlusion: None,
+            shading: Shading::Phong,
+            crease_angle: DEFAULT_CREASE_ANGLE,
+            mesh_normals_crease_angle: None,
+            lod_mesh_normals_crease_angle: None,
             near: 6.0,
             far: 10.0,
         }
@@ -92,6 +153,25 @@ impl Renderer {
 
     pub fn set_mesh(&mut self, mesh: Mesh) {
         self.mesh = mesh;
+        self.lod_mesh = None;
+        self.mesh_normals_crease_angle = None;
+        self.lod_mesh_normals_crease_angle = None;
+        self.mesh_culling = None;
+        self.lod_mesh_culling = None;
+        self.shadow_maps = None;
+    }
+
+    /// Moves the points of `mesh`, for example to explode it
+    pub fn set_mesh_points(&mut self, points: Vec<Point3>) {
+        self.mesh.points = points;
+        self.shadow_maps = None;
+    }
+
+    pub fn set_lod_mesh(&mut self, lod_mesh: Option<Mesh>) {
+        self.lod_mesh = lod_mesh;
+        self.lod_mesh_normals_crease_angle = None;
+        self.lod_mesh_culling = None;
+        self.shadow_maps = None;
     }
 
     fn prepare_for_rendering(&self) {
@@ -105,15 +185,104 @@ impl Renderer {
     pub fn render_frame(&mut self) {
         self.clear_image_buffer();
         self.clear_depth_buffer();
+        if self.shading != Shading::Flat {
+            add_missing_vertex_normals(
+                &mut self.mesh,
+                self.crease_angle,
+                &mut self.mesh_normals_crease_angle,
+            );
+            if let Some(lod_mesh) = &mut self.lod_mesh {
+                add_missing_vertex_normals(
+                    lod_mesh,
+                    self.crease_angle,
+                    &mut self.lod_mesh_normals_crease_angle,
+                );
+            }
+        }
+        // the shadow maps cover the whole mesh, which usually fills about the same area on the screen
+        let shadow_map_size = SHADOW_MAP_RESOLUTION * self.sample_width().max(self.sample_height());
+        let (mesh, culling) = match &self.lod_mesh {
+            Some(lod_mesh) if self.use_lod_mesh => (lod_mesh, &mut self.lod_mesh_culling),
+            _ => (&self.mesh, &mut self.mesh_culling),
+        };
+        if !culling.as_ref().is_some_and(|culling| culling.fits(mesh)) {
+            *culling = GroupCulling::new(mesh);
+        }
+        let use_lod_mesh = self.use_lod_mesh && self.lod_mesh.is_some();
+        let shadow_maps_fit = self.shadow_maps.as_ref().is_some_and(|shadow_maps| {
+            shadow_maps.lights == self.lights
+                && shadow_maps.use_lod_mesh == use_lod_mesh
+                && shadow_maps.size == shadow_map_size
+        });
+        if self.shadows && !shadow_maps_fit {
+            self.shadow_maps = Some(ShadowMaps {
+                lights: self.lights.clone(),
+                use_lod_mesh,
+                size: shadow_map_size,
+                maps: self
+                    .lights
+                    .iter()
+                    .map(|light| ShadowMap::new(mesh, light, shadow_map_size))
+                    .collect(),
+            });
+        }
+        // lent to the lighting for this frame
+        let shadow_maps = match &mut self.shadow_maps {
+            Some(shadow_maps) if self.shadows => std::mem::take(&mut shadow_maps.maps),
+            _ => Vec::new(),
+        };
+        let lighting = Lighting {
+            lights: self.lights.clone(),
+            ambient_light: self.ambient_light,
+            camera_position: self.camera.position.clone(),
+            shadow_maps,
+        };
+        let surface = Surface {
+            shader: self.shader.as_ref(),
+            lighting: &lighting,
+            shading: self.shading,
+            vertex_colors: self.vertex_colors.as_deref(),
+        };
         render_mesh(
-            &self.mesh,
+            mesh,
             &mut self.image_buffer,
             &mut self.depth_buffer,
+            &mut self.transformed_points,
+            &self.camera,
+            &surface,
+            culling.as_ref(),
+        );
+        if let Some(shadow_maps) = &mut self.shadow_maps {
+            if self.shadows {
+                shadow_maps.maps = lighting.shadow_maps;
+            }
+        }
+        let samples_per_char = self.samples_per_char();
+        if let Some(ambient_occlusion) = &self.ambient_occlusion {
+            ambient_occlusion.apply(
+                &mut self.image_buffer,
+                &self.depth_buffer,
+                &self.camera,
+                samples_per_char,
+            );
+        }
+        let image_buffer = if samples_per_char > 1 {
+            downsample_image_buffer(
+                &self.image_buffer,
+                samples_per_char,
+                &mut self.downsampled_image_buffer,
+            );
+            &self.downsampled_image_buffer
+        } else {
+            &self.image_buffer
+        };
+        image_buffer_to_char_buffer(image_buffer, &mut self.char_buffer, &self.chars);
+        render_overlay_lines(
+            &self.overlay_lines,
+            &mut self.char_buffer,
             &self.camera,
-            &self.light_direction,
-            self.ambient_light,
+            OVERLAY_LINE_CHAR,
         );
-        image_buffer_to_char_buffer(&self.image_buffer, &mut self.char_buffer, &self.chars);
         add_info_line_to_char_buffer(&mut self.char_buffer, &self.info_line);
         draw_char_buffer(&self.char_buffer, &self.prev_char_buffer);
 
@@ -132,6 +301,9 @@ impl Renderer {
             if events.iter().any(|event| should_exit(event)) {
                 break;
             }
+            if events.iter().any(toggles_shadows) {
+                self.shadows = !self.shadows;
+            }
             call_every_frame(self, events);
             self.render_frame();
 
@@ -164,11 +336,53 @@ impl Renderer {
     }
 
     fn clear_image_buffer(&mut self) {
-        self.image_buffer = Buffer::new(self.width as usize, self.height as usize, 0.0);
+        let (width, height) = (self.sample_width(), self.sample_height());
+        self.image_buffer.clear(width, height, 0.0);
     }
 
     fn clear_depth_buffer(&mut self) {
-        self.depth_buffer = Buffer::new(self.width as usize, self.height as usize, f32::MAX);
+        let (width, height) = (self.sample_width(), self.sample_height());
+        self.depth_buffer.clear(width, height, f32::MAX);
+    }
+
+    fn samples_per_char(&self) -> usize {
+        self.supersampling.max(1) as usize
+    }
+
+    fn sample_width(&self) -> usize {
+        self.width as usize * self.samples_per_char()
+    }
+
+    fn sample_height(&self) -> usize {
+        self.height as usize * self.samples_per_char()
+    }
+}
+
+/// The shadow map of each light, with what they were made for
+struct ShadowMaps {
+    lights: Vec<Light>,
+    use_lod_mesh: bool,
+    size: usize,
+    maps: Vec<Option<ShadowMap>>,
+}
+
+/// Meshes from files without vertex normals, and subdivided or simplified meshes, have none.
+/// Many exporters write one normal per face, which would look the same as flat shading, so those are replaced as well.
+/// Generated normals are generated again when the crease angle changed since.
+fn add_missing_vertex_normals(
+    mesh: &mut Mesh,
+    crease_angle: Degrees,
+    generated_crease_angle: &mut Option<Degrees>,
+) {
+    let has_smooth_normals = mesh
+        .face_data
+        .normal_indices
+        .as_ref()
+        .is_some_and(|indices| indices.iter().any(|[a, b, c]| a != b || b != c));
+    let is_outdated = generated_crease_angle.is_some_and(|angle| angle != crease_angle);
+    if (!has_smooth_normals || is_outdated) && mesh.triangle_count() > 0 {
+        mesh.generate_vertex_normals(crease_angle, NormalWeighting::Angle);
+        *generated_crease_angle = Some(crease_angle);
     }
 }
 
@@ -188,7 +402,7 @@ fn get_vertical_fov(diagonal_fov: f32, aspect_ratio: f32) -> f32 {
         .to_degrees()
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct Buffer<T: Copy> {
     pub values: Vec<T>,
     pub height: usize,
@@ -204,6 +418,15 @@ impl<T: Copy> Buffer<T> {
         }
     }
 
+    /// Sets every value and changes the size to `width` × `height`.
+    /// Only allocates if the buffer grows
+    pub fn clear(&mut self, width: usize, height: usize, fill_value: T) {
+        self.values.clear();
+        self.values.resize(width * height, fill_value);
+        self.width = width;
+        self.height = height;
+    }
+
     pub fn get(&self, x: usize, y: usize) -> Option<T> {
         match self.get_index(x, y) {
             None => None,
@@ -236,3 +459,26 @@ impl<T: Copy> Buffer<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh_processing::test_meshes::cube;
+
+    #[test]
+    fn test_changing_the_crease_angle_regenerates_the_vertex_normals() {
+        let mut renderer = Renderer::new(20, 10, 30.0, 2.0, 90.0, " .:-=+*#%@");
+        renderer.set_mesh(cube());
+        renderer.render_frame();
+        // the edges of the cube are 90 degrees, so every corner has one normal per side
+        assert_eq!(renderer.mesh.normals.len(), 24);
+
+        renderer.crease_angle = Degrees(100.0);
+        renderer.render_frame();
+        assert_eq!(renderer.mesh.normals.len(), 8);
+
+        renderer.crease_angle = DEFAULT_CREASE_ANGLE;
+        renderer.render_frame();
+        assert_eq!(renderer.mesh.normals.len(), 24);
+    }
+}
@@ -1,6 +1,11 @@
 pub mod camera_rotation;
 pub mod events;
+pub mod explode_input;
 pub mod interface;
+pub mod mtl_parser;
 pub mod obj_parser;
+pub mod pick_input;
 pub mod pipeline;
 pub mod render;
+pub mod section_input;
+pub mod subdivision_input;
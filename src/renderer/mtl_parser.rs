@@ -0,0 +1,148 @@
+use crate::general::material::Material;
+use std::fs;
+use std::path::Path;
+
+/// Reads the materials of .mtl files, which .obj files reference with `mtllib`.
+/// Only the properties that are used for lighting are read, other statements are skipped.
+pub struct MtlParser {
+    materials: Vec<Material>,
+}
+
+impl MtlParser {
+    pub fn parse_file(file_path: &Path) -> Result<Vec<Material>, String> {
+        let content = fs::read_to_string(file_path).map_err(|_| {
+            format!(
+                "Could not read material file '{}'",
+                file_path.to_string_lossy()
+            )
+        })?;
+        MtlParser::parse_str(&content).map_err(|message| {
+            format!(
+                "{message}\nIn material file '{}'",
+                file_path.to_string_lossy()
+            )
+        })
+    }
+
+    pub fn parse_str(content: &str) -> Result<Vec<Material>, String> {
+        let mut mtl_parser = MtlParser {
+            materials: Vec::new(),
+        };
+        for (line_index, line) in content.lines().enumerate() {
+            mtl_parser
+                .handle_line(line)
+                .map_err(|message| format!("{message}\nAt line {}: '{line}'", line_index + 1))?;
+        }
+        Ok(mtl_parser.materials)
+    }
+
+    fn handle_line(&mut self, line: &str) -> Result<(), String> {
+        let space_separated_strings: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command_string, argument_strings)) = space_separated_strings.split_first()
+        else {
+            return Ok(());
+        };
+
+        if command_string == "newmtl" {
+            self.materials
+                .push(Material::new(&argument_strings.join(" ")));
+            return Ok(());
+        }
+        let property = match command_string {
+            "Ks" => parse_color(argument_strings)?,
+            "Ns" => Some(parse_number(argument_strings)?),
+            _ => return Ok(()),
+        };
+        let Some(property) = property else {
+            return Ok(());
+        };
+        let material = self
+            .materials
+            .last_mut()
+            .ok_or("material property before the first newmtl")?;
+        match command_string {
+            "Ks" => material.specular = Some(property),
+            _ => material.shininess = Some(property),
+        }
+        Ok(())
+    }
+}
+
+/// The average of the red, green and blue components.
+/// None for colors given as CIE XYZ or as a spectral curve, which are not supported
+fn parse_color(argument_strings: &[&str]) -> Result<Option<f32>, String> {
+    if matches!(argument_strings.first(), Some(&"xyz" | &"spectral")) {
+        return Ok(None);
+    }
+    let components = argument_strings
+        .iter()
+        .map(|string| string.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| "error when parsing color")?;
+    match components.as_slice() {
+        // a single value is used for all three components
+        [value] => Ok(Some(*value)),
+        [red, green, blue] => Ok(Some((red + green + blue) / 3.0)),
+        _ => Err("invalid amount of color components (should be 1 or 3)".into()),
+    }
+}
+
+fn parse_number(argument_strings: &[&str]) -> Result<f32, String> {
+    match argument_strings {
+        [string] => string
+            .parse()
+            .map_err(|_| "error when parsing number".into()),
+        _ => Err("invalid amount of numbers (should be 1)".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MtlParser;
+
+    #[test]
+    fn test_lighting_properties_are_read() {
+        let materials = MtlParser::parse_str(
+            "# comment\n\
+             newmtl Metal\n\
+             Ns 225.0\n\
+             Kd 0.8 0.8 0.8\n\
+             Ks 0.0 0.0 0.3\n\
+             illum 2\n\
+             \n\
+             newmtl Plastic Red\n\
+             Ks 0.8\n",
+        )
+        .unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].name, "Metal");
+        assert_eq!(materials[0].shininess, Some(225.0));
+        assert!((materials[0].specular.unwrap() - 0.1).abs() < 1e-6);
+        assert_eq!(materials[1].name, "Plastic Red");
+        assert_eq!(materials[1].specular, Some(0.8));
+        assert_eq!(materials[1].shininess, None);
+    }
+
+    #[test]
+    fn test_unsupported_color_forms_are_skipped() {
+        let materials = MtlParser::parse_str(
+            "newmtl Xyz\n\
+             Ks xyz 0.5 0.5 0.5\n\
+             newmtl Spectral\n\
+             Ks spectral steel.rfl 1.5\n\
+             Ns 100\n",
+        )
+        .unwrap();
+        assert_eq!(materials.len(), 2);
+        assert_eq!(materials[0].specular, None);
+        assert_eq!(materials[1].specular, None);
+        assert_eq!(materials[1].shininess, Some(100.0));
+    }
+
+    #[test]
+    fn test_invalid_properties_are_errors() {
+        assert!(MtlParser::parse_str("Ks 0.5 0.5 0.5").is_err());
+        assert!(MtlParser::parse_str("newmtl a\nKs 0.5 0.5").is_err());
+        assert!(MtlParser::parse_str("newmtl a\nNs shiny").is_err());
+    }
+}
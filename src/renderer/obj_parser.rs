@@ -1,12 +1,25 @@
-use crate::general::positions_3d::{IndicesTriangle, Mesh, Point as Point3, Triangle as Triangle3};
+use crate::general::material::Material;
+use crate::general::positions_2d::Point as Point2;
+use crate::general::positions_3d::{GlobalPoint, Mesh, Point as Point3, Triangle as Triangle3};
+use crate::renderer::mtl_parser::MtlParser;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{self, BufRead};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Meshes with vertices further away from the origin than this are stored relative to their center.
+/// Up to this distance, `f32` coordinates still have about millimetre precision.
+const REBASE_DISTANCE: f64 = 10_000.0;
 
 pub struct ObjParser {
-    normals: Vec<Point3>,
     mesh: Mesh,
+    /// The vertices with double precision, they are moved into `mesh` once the origin is known
+    global_points: Vec<GlobalPoint>,
+    current_group: Option<usize>,
+    current_material: Option<usize>,
+    /// File names from `mtllib`, relative to the .obj file
+    material_libraries: Vec<String>,
+    polygon_count: usize,
 }
 
 enum LineParseResult {
@@ -27,12 +40,26 @@ impl From<Result<(), String>> for LineParseResult {
 impl ObjParser {
     fn new() -> Self {
         ObjParser {
-            normals: Vec::new(),
             mesh: Mesh::new(),
+            global_points: Vec::new(),
+            current_group: None,
+            current_material: None,
+            material_libraries: Vec::new(),
+            polygon_count: 0,
         }
     }
-    fn add_vertex(&mut self, vertex: Point3) {
-        self.mesh.points.push(vertex);
+    /// The positions are read with double precision and stored relative to `Mesh::origin`,
+    /// which is chosen from all of them. The winding of the triangles is fixed once the points are known
+    fn move_points_into_mesh(&mut self) {
+        self.mesh.origin = rebased_origin(&self.global_points);
+        self.mesh.points = self
+            .global_points
+            .iter()
+            .map(|position| self.mesh.origin.local_offset_to(position))
+            .collect();
+        for triangle in 0..self.mesh.triangle_count() {
+            self.mesh.make_clockwise(triangle);
+        }
     }
 
     pub fn parse_file(file_path: &PathBuf) -> Result<Mesh, String> {
@@ -71,9 +98,11 @@ impl ObjParser {
         if parsed_lines == 0 {
             return Err(String::from("did not find any obj data"));
         }
-        if obj_parser.mesh.indices_triangles.len() == 0 {
+        if obj_parser.mesh.triangle_count() == 0 {
             return Err(String::from("returned a mesh without any triangles"));
         }
+        obj_parser.move_points_into_mesh();
+        obj_parser.load_material_libraries(file_path);
 
         Ok(obj_parser.mesh)
     }
@@ -90,7 +119,15 @@ impl ObjParser {
         match command_string {
             "v" => self.handle_v(argument_strings).into(),
             "vn" => self.handle_vn(argument_strings).into(),
+            "vt" => self.handle_vt(argument_strings).into(),
             "f" => self.handle_f(argument_strings).into(),
+            "g" | "o" => self.handle_g(argument_strings).into(),
+            "usemtl" => self.handle_usemtl(argument_strings).into(),
+            "mtllib" => {
+                let name = argument_strings.join(" ");
+                self.material_libraries.push(name);
+                LineParseResult::Parsed
+            }
             _ => LineParseResult::Skipped,
         }
     }
@@ -104,13 +141,14 @@ impl ObjParser {
             return Err("invalid amount of coordinate components (should be 3)".into());
         }
 
-        let argument_nums: Vec<f32> = argument_strings
+        let argument_nums: Vec<f64> = argument_strings
             .iter()
             .filter_map(|str| str.parse().ok())
             .collect();
 
-        if argument_nums.len() == argument_strings.len() {
-            self.add_vertex(Point3::from_vec(argument_nums).unwrap());
+        if let Ok(coordinates) = argument_nums.try_into() {
+            self.global_points
+                .push(GlobalPoint::from_array(coordinates));
             return Ok(());
         } else {
             return Err("error when parsing verts".into());
@@ -131,35 +169,120 @@ impl ObjParser {
         if argument_nums.len() != argument_strings.len() {
             return Err(String::from("error when parsing vertex normal vector"));
         }
-        self.normals.push(Point3::from_vec(argument_nums).unwrap());
+        self.mesh
+            .normals
+            .push(Point3::from_vec(argument_nums).unwrap());
         Ok(())
     }
 
-    fn handle_f(&mut self, argument_strings: &[&str]) -> Result<(), String> {
-        if argument_strings.len() > 4 {
+    fn handle_vt(&mut self, argument_strings: &[&str]) -> Result<(), String> {
+        if argument_strings.len() > 3 {
             return Err(String::from(
-                "faces with more than 4 vertices are not supported",
+                "invalid amount of texture coordinate components (should be 1 to 3)",
             ));
         }
 
-        let first_three_vertex_strings = &argument_strings[0..3].try_into().or(Result::Err(
-            "face declaration must have at least three vertices",
-        ))?;
-        self.handle_triangle_face(first_three_vertex_strings)?;
+        let argument_nums: Vec<f32> = argument_strings
+            .iter()
+            .filter_map(|str| str.parse().ok())
+            .collect();
+        if argument_nums.len() != argument_strings.len() {
+            return Err(String::from("error when parsing texture coordinates"));
+        }
+        self.mesh.uvs.push(Point2 {
+            x: argument_nums[0],
+            y: argument_nums.get(1).copied().unwrap_or(0.0),
+        });
+        Ok(())
+    }
+
+    /// Handles both groups (`g`) and objects (`o`)
+    fn handle_g(&mut self, argument_strings: &[&str]) -> Result<(), String> {
+        let name = argument_strings.join(" ");
+        let group_index = match self.mesh.groups.iter().position(|group| *group == name) {
+            Some(index) => index,
+            None => {
+                self.mesh.groups.push(name);
+                self.mesh.groups.len() - 1
+            }
+        };
+        self.current_group = Some(group_index);
+        Ok(())
+    }
+
+    fn handle_usemtl(&mut self, argument_strings: &[&str]) -> Result<(), String> {
+        let name = argument_strings.join(" ");
+        let materials = &mut self.mesh.materials;
+        let material_index = match materials.iter().position(|material| material.name == name) {
+            Some(index) => index,
+            None => {
+                materials.push(Material::new(&name));
+                materials.len() - 1
+            }
+        };
+        self.current_material = Some(material_index);
+        Ok(())
+    }
 
-        if argument_strings.len() == 4 {
-            let vertex_strings_2 = [
-                argument_strings[2],
-                argument_strings[3],
+    /// Reads the properties of the used materials from the `mtllib` files.
+    /// Missing files are skipped, since many .obj files are shared without them.
+    /// Materials only change the lighting, so libraries that can not be read are skipped with a warning
+    /// and their materials keep the default lighting properties
+    fn load_material_libraries(&mut self, obj_path: &Path) {
+        let directory = obj_path.parent().unwrap_or(Path::new(""));
+        for library in &self.material_libraries {
+            let library_path = directory.join(library);
+            if !library_path.is_file() {
+                continue;
+            }
+            let library_materials = match MtlParser::parse_file(&library_path) {
+                Ok(library_materials) => library_materials,
+                Err(message) => {
+                    eprintln!("Warning, skipped the materials: {message}");
+                    continue;
+                }
+            };
+            for library_material in library_materials {
+                if let Some(material) = self
+                    .mesh
+                    .materials
+                    .iter_mut()
+                    .find(|material| material.name == library_material.name)
+                {
+                    *material = library_material;
+                }
+            }
+        }
+    }
+
+    /// Polygons with more than three vertices are split into a fan of triangles,
+    /// which remember the polygon they belong to
+    fn handle_f(&mut self, argument_strings: &[&str]) -> Result<(), String> {
+        if argument_strings.len() < 3 {
+            return Err(String::from(
+                "face declaration must have at least three vertices",
+            ));
+        }
+
+        let polygon = self.polygon_count;
+        self.polygon_count += 1;
+        for i in 1..argument_strings.len() - 1 {
+            let vertex_strings = [
                 argument_strings[0],
+                argument_strings[i],
+                argument_strings[i + 1],
             ];
-            self.handle_triangle_face(&vertex_strings_2)?;
+            self.handle_triangle_face(&vertex_strings, polygon)?;
         }
 
         Ok(())
     }
 
-    fn handle_triangle_face(&mut self, vertex_strings: &[&str; 3]) -> Result<(), String> {
+    fn handle_triangle_face(
+        &mut self,
+        vertex_strings: &[&str; 3],
+        polygon: usize,
+    ) -> Result<(), String> {
         let parsed_vertex_numbers: Vec<[Option<usize>; 3]> = vertex_strings
             .iter()
             .map(|str| parse_face_element_vertex_string(str))
@@ -167,19 +290,35 @@ impl ObjParser {
 
         let vertices_indices: Vec<usize> = parsed_vertex_numbers
             .iter()
-            .map(|indices| indices[0].expect("vertex index in face declaration") - 1)
+            .map(|indices| match indices[0] {
+                Some(index) => zero_based_index(index, self.global_points.len(), "vertex"),
+                None => Err(String::from("missing vertex index in face declaration")),
+            })
+            .collect::<Result<_, _>>()?;
+
+        // relative to the first vertex, so that far away triangles keep their precision
+        let first_vertex = &self.global_points[vertices_indices[0]];
+        let vertices: Vec<Point3> = vertices_indices
+            .iter()
+            .map(|vertex_index| first_vertex.local_offset_to(&self.global_points[*vertex_index]))
             .collect();
+        let vertices: Vec<&Point3> = vertices.iter().collect();
 
-        let vertices: Vec<&Point3> = vertices_indices
+        let uv_indices: Vec<usize> = parsed_vertex_numbers
             .iter()
-            .map(|vertex_index| &self.mesh.points[*vertex_index])
-            .collect();
+            .filter_map(|indices| indices[1])
+            .map(|index| zero_based_index(index, self.mesh.uvs.len(), "texture coordinate"))
+            .collect::<Result<_, _>>()?;
 
-        let vertex_normals: Vec<&Point3> = parsed_vertex_numbers
+        let normal_indices: Vec<usize> = parsed_vertex_numbers
             .iter()
             .filter_map(|indices| indices[2])
-            .map(|one_based_index| one_based_index - 1)
-            .map(|normal_index| &self.normals[normal_index])
+            .map(|index| zero_based_index(index, self.mesh.normals.len(), "vertex normal"))
+            .collect::<Result<_, _>>()?;
+
+        let vertex_normals: Vec<&Point3> = normal_indices
+            .iter()
+            .map(|normal_index| &self.mesh.normals[*normal_index])
             .collect();
 
         if vertex_normals.len() > 0 && vertex_normals.len() < 3 {
@@ -203,18 +342,55 @@ impl ObjParser {
         };
 
         // TODO support negative indices
-        let mut triangle = IndicesTriangle {
-            p1: vertices_indices[0],
-            p2: vertices_indices[1],
-            p3: vertices_indices[2],
-            normal: triangle_normal,
-        };
-        triangle.make_clockwise(&self.mesh.points);
-        self.mesh.indices_triangles.push(triangle);
+        let triangle = self.mesh.push_triangle(
+            [
+                vertices_indices[0],
+                vertices_indices[1],
+                vertices_indices[2],
+            ],
+            triangle_normal,
+        );
+        if let Ok(normal_indices) = normal_indices.try_into() {
+            self.mesh.set_normal_indices(triangle, normal_indices);
+        }
+        if let Ok(uv_indices) = uv_indices.try_into() {
+            self.mesh.set_uv_indices(triangle, uv_indices);
+        }
+        if let Some(group) = self.current_group {
+            self.mesh.set_group(triangle, group);
+        }
+        if let Some(material) = self.current_material {
+            self.mesh.set_material(triangle, material);
+        }
+        self.mesh.set_polygon(triangle, polygon);
         Ok(())
     }
 }
 
+/// A whole-numbered position close to the centroid of the points if any of them is far away from the origin,
+/// otherwise zero. Unlike the center of the bounding box, a few stray points hardly move the centroid
+fn rebased_origin(points: &[GlobalPoint]) -> GlobalPoint {
+    let finite_points: Vec<[f64; 3]> = points
+        .iter()
+        .map(GlobalPoint::to_array)
+        .filter(|point| point.iter().all(|component| component.is_finite()))
+        .collect();
+    let is_far_away = finite_points
+        .iter()
+        .flatten()
+        .any(|component| component.abs() > REBASE_DISTANCE);
+    if !is_far_away {
+        return GlobalPoint::default();
+    }
+    let mut sum = [0.0; 3];
+    for point in &finite_points {
+        for axis in 0..3 {
+            sum[axis] += point[axis];
+        }
+    }
+    GlobalPoint::from_array(sum.map(|sum| (sum / finite_points.len() as f64).round()))
+}
+
 fn read_lines(file_path: &PathBuf) -> io::Result<io::Lines<io::BufReader<File>>> {
     let file = File::open(file_path)?;
     Ok(io::BufReader::new(file).lines())
@@ -223,6 +399,17 @@ fn read_lines(file_path: &PathBuf) -> io::Result<io::Lines<io::BufReader<File>>>
 /**
  * example inputs: "3/4/5", "3", "3//4", "3//"
  */
+/// Face elements refer to vertices, texture coordinates and normals by one-based indices,
+/// which have to be declared before the face
+fn zero_based_index(one_based_index: usize, count: usize, name: &str) -> Result<usize, String> {
+    if one_based_index == 0 || one_based_index > count {
+        return Err(format!(
+            "{name} index {one_based_index} out of range, {count} were declared before the face"
+        ));
+    }
+    Ok(one_based_index - 1)
+}
+
 fn parse_face_element_vertex_string(string: &str) -> [Option<usize>; 3] {
     let substrings: Vec<&str> = string.split('/').collect();
     let mut numbers = [None, None, None];
@@ -241,3 +428,75 @@ pub fn all_equal<T: PartialEq>(elements: &[T]) -> Option<bool> {
     let first = elements.get(0)?;
     Some(elements.iter().all(|elem| elem == first))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ObjParser;
+    use crate::general::positions_3d::Mesh;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Writes the files into a new directory in the temporary directory and parses the .obj file
+    fn parse_files(name: &str, obj: &str, mtl: &str) -> Result<Mesh, String> {
+        let directory =
+            std::env::temp_dir().join(format!("obj_parser_{name}_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("model.mtl"), mtl).unwrap();
+        let obj_path: PathBuf = directory.join("model.obj");
+        fs::write(&obj_path, obj).unwrap();
+        let result = ObjParser::parse_file(&obj_path);
+        fs::remove_dir_all(&directory).unwrap();
+        result
+    }
+
+    const TRIANGLE: &str = "mtllib model.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl Metal\nf 1 2 3\n";
+
+    #[test]
+    fn test_invalid_material_library_is_skipped() {
+        let mesh = parse_files("invalid_mtl", TRIANGLE, "newmtl Metal\nKs 0.5 0.5\n").unwrap();
+        assert_eq!(mesh.triangle_count(), 1);
+        assert_eq!(mesh.materials[0].name, "Metal");
+        assert_eq!(mesh.materials[0].specular, None);
+
+        let mesh = parse_files("valid_mtl", TRIANGLE, "newmtl Metal\nKs 0.5 0.5 0.5\n").unwrap();
+        assert_eq!(mesh.materials[0].specular, Some(0.5));
+    }
+
+    #[test]
+    fn test_far_away_mesh_is_rebased_around_its_center() {
+        // georeferenced points with a stray point at the origin, which comes first in the file
+        let mut obj = String::from("v 0 0 0\n");
+        for i in 0..1000 {
+            obj += &format!("v {} 1000000.5 {}\n", 2_500_000.0 + i as f64 * 0.001, i % 2);
+        }
+        obj += "f 2 3 4\n";
+        let mesh = parse_files("rebase", &obj, "").unwrap();
+        let origin = mesh.origin.to_array();
+        assert!((origin[0] - 2_500_000.0).abs() < 10_000.0);
+        assert!((origin[1] - 1_000_000.0).abs() < 10_000.0);
+        let second_point = mesh.to_global(&mesh.points[2]).to_array();
+        assert!((second_point[0] - 2_500_000.001).abs() < 1e-4);
+        assert_eq!(second_point[1], 1_000_000.5);
+    }
+
+    #[test]
+    fn test_out_of_range_face_indices_are_errors() {
+        let declarations = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n";
+        for (name, face) in [
+            ("vertex", "f 1 2 4"),
+            ("zero_vertex", "f 0 1 2"),
+            ("missing_vertex", "f 1 2 /1"),
+            ("texture_coordinate", "f 1/1 2/1 3/2"),
+            ("normal", "f 1//1 2//2 3//1"),
+        ] {
+            let result = parse_files(name, &format!("{declarations}{face}\n"), "");
+            assert!(result.is_err(), "{face}");
+        }
+        let mesh = parse_files(
+            "valid_indices",
+            &format!("{declarations}f 1/1/1 2/1/1 3/1/1\n"),
+            "",
+        );
+        assert_eq!(mesh.unwrap().triangle_count(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+use super::interface::Camera;
+use super::render::pixel_ray;
+use crate::general::positions_3d::{distance, GlobalPoint, Mesh, Point as Point3};
+use crate::mesh_processing::bvh::Bvh;
+use crossterm::event::{Event, KeyCode, MouseButton, MouseEventKind};
+
+/// Picks points on the mesh with the mouse, to measure distances and to choose the orbit pivot.
+/// Click the right mouse button on the model to pick the point below the cursor,
+/// the distance to the previously picked point is shown as well. Clicking next to the model clears the points.
+/// Press `p` to orbit around the last picked point.
+pub struct PickInputHelper {
+    /// Built on the first pick, since that takes a while for large meshes
+    bvh: Option<Bvh>,
+    /// The last two picked points, the newest one last
+    picked_points: Vec<Point3>,
+    pivot: Option<Point3>,
+    /// The `Mesh::origin` of the mesh, positions are shown in global coordinates
+    origin: GlobalPoint,
+    has_changed: bool,
+}
+
+impl PickInputHelper {
+    pub fn new(origin: GlobalPoint) -> Self {
+        PickInputHelper {
+            bvh: None,
+            picked_points: Vec::new(),
+            pivot: None,
+            origin,
+            has_changed: false,
+        }
+    }
+
+    /// Has to be called when the mesh changes, for example when it is subdivided
+    pub fn mesh_changed(&mut self) {
+        self.bvh = None;
+    }
+
+    /// `width` and `height` are the size of the rendered image, in characters
+    pub fn process_input_events(
+        &mut self,
+        events: &[Event],
+        mesh: &Mesh,
+        camera: &Camera,
+        width: usize,
+        height: usize,
+    ) {
+        for event in events {
+            match event {
+                Event::Mouse(mouse_event)
+                    if mouse_event.kind == MouseEventKind::Down(MouseButton::Right) =>
+                {
+                    let Some(ray) = pixel_ray(
+                        camera,
+                        mouse_event.column as usize,
+                        mouse_event.row as usize,
+                        width,
+                        height,
+                    ) else {
+                        continue;
+                    };
+                    let bvh = self.bvh.get_or_insert_with(|| Bvh::new(mesh));
+                    match bvh.cast_ray(&ray) {
+                        Some(hit) => {
+                            if self.picked_points.len() == 2 {
+                                self.picked_points.remove(0);
+                            }
+                            self.picked_points.push(hit.point);
+                        }
+                        None => self.picked_points.clear(),
+                    }
+                    self.has_changed = true;
+                }
+                Event::Key(key_event) if key_event.code == KeyCode::Char('p') => {
+                    self.pivot = self.picked_points.last().cloned();
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns true once after a point was picked or the points were cleared
+    pub fn take_has_changed(&mut self) -> bool {
+        std::mem::take(&mut self.has_changed)
+    }
+
+    /// Returns the point to orbit around once after `p` was pressed
+    pub fn take_pivot(&mut self) -> Option<Point3> {
+        self.pivot.take()
+    }
+
+    /// The line between the two picked points, to draw on top of the mesh
+    pub fn measurement_line(&self) -> Option<[Point3; 2]> {
+        match self.picked_points.as_slice() {
+            [first, second] => Some([first.clone(), second.clone()]),
+            _ => None,
+        }
+    }
+
+    /// A short description like "picked 1 2 3" or "distance 2.5 to 1 2 3", or `None` if nothing is picked
+    pub fn description(&self) -> Option<String> {
+        let last = self.picked_points.last()?;
+        let GlobalPoint { x, y, z } = self.origin.add_local_rounded(last);
+        Some(match self.measurement_line() {
+            Some([first, second]) => {
+                format!("distance {} to {x} {y} {z}", distance(&first, &second))
+            }
+            None => format!("picked {x} {y} {z}"),
+        })
+    }
+}
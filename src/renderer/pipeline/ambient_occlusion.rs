@@ -0,0 +1,191 @@
+use crate::renderer::interface::Buffer;
+use crate::renderer::pipeline::transformation::Camera;
+
+/// Directions of the sample pairs around each pixel, before correcting for the shape of the pixels
+const SAMPLE_DIRECTIONS: [[f32; 2]; 4] = [
+    [1.0, 0.0],
+    [0.0, 1.0],
+    [0.70710677, 0.70710677],
+    [0.70710677, -0.70710677],
+];
+/// Distances of the sample pairs as fractions of the radius
+const SAMPLE_DISTANCES: [f32; 3] = [1.0 / 3.0, 2.0 / 3.0, 1.0];
+/// Crevices shallower than this fraction of the radius are ignored,
+/// so that finely tessellated curved surfaces and rounding errors do not darken the image
+const MIN_CREVICE_DEPTH: f32 = 0.05;
+
+/// Screen-space ambient occlusion, darkens crevices and the areas where parts touch.
+///
+/// Samples pairs of opposite pixels of the depth buffer around each pixel.
+/// If they are closer to the camera than the plane through the pixel between them,
+/// the pixel is in a crevice and is darkened, the deeper the darker.
+/// Much closer surfaces, like a part in front of another one, do not darken it.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::renderer::pipeline::ambient_occlusion::AmbientOcclusion;
+///
+/// // wide, subtle shading of the crevices
+/// let ambient_occlusion = AmbientOcclusion {
+///     radius: 3.0,
+///     strength: 0.4,
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientOcclusion {
+    /// How far around each pixel the depth buffer is sampled, in characters of the terminal
+    pub radius: f32,
+    /// Brightness that fully occluded pixels lose, from 0 (no effect) to 1 (black)
+    pub strength: f32,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        AmbientOcclusion {
+            radius: 1.5,
+            strength: 0.7,
+        }
+    }
+}
+
+impl AmbientOcclusion {
+    /// Darkens `image_buffer` based on `depth_buffer`, which it was rendered with from `camera`.
+    /// `samples_per_char` is the supersampling factor of the buffers.
+    pub fn apply(
+        &self,
+        image_buffer: &mut Buffer<f32>,
+        depth_buffer: &Buffer<f32>,
+        camera: &Camera,
+        samples_per_char: usize,
+    ) {
+        let (near, far) = (camera.near, camera.far);
+        // size of a pixel one unit in front of the camera
+        let pixel_width =
+            2.0 * (camera.horizontal_fov.to_radians() / 2.0).tan() / depth_buffer.width as f32;
+        let pixel_height =
+            2.0 * (camera.vertical_fov.to_radians() / 2.0).tan() / depth_buffer.height as f32;
+        let radius = self.radius * samples_per_char as f32;
+        // pixels are taller than wide, so vertical offsets are shorter to sample a circle on the surface
+        let offsets: Vec<[isize; 2]> = SAMPLE_DIRECTIONS
+            .iter()
+            .flat_map(|[x, y]| {
+                SAMPLE_DISTANCES.map(|distance| {
+                    [
+                        (x * distance * radius).round() as isize,
+                        (y * distance * radius * pixel_width / pixel_height).round() as isize,
+                    ]
+                })
+            })
+            .filter(|&offset| offset != [0, 0])
+            .collect();
+        let depth_at = |x: usize, y: usize, [offset_x, offset_y]: [isize; 2]| {
+            let x = x.checked_add_signed(offset_x)?;
+            let y = y.checked_add_signed(offset_y)?;
+            // pixels without a surface are not taken into account
+            depth_buffer.get(x, y).filter(|&depth| depth <= 1.0)
+        };
+
+        for y in 0..depth_buffer.height {
+            for x in 0..depth_buffer.width {
+                let depth = depth_buffer.get(x, y).unwrap();
+                if depth > 1.0 {
+                    continue;
+                }
+                // the depth buffer goes from 0 at the near plane to 1 at the far plane, but not linearly
+                let distance = near * far / (far - depth * (far - near));
+                let world_units_per_depth = distance * distance * (far - near) / (near * far);
+                let world_radius = radius * pixel_width * distance;
+
+                let mut occlusion = 0.0;
+                let mut pair_count = 0;
+                for &[offset_x, offset_y] in &offsets {
+                    let (Some(depth1), Some(depth2)) = (
+                        depth_at(x, y, [offset_x, offset_y]),
+                        depth_at(x, y, [-offset_x, -offset_y]),
+                    ) else {
+                        continue;
+                    };
+                    pair_count += 1;
+                    // the depth of planes changes linearly across the screen, so planes are not occluded
+                    let crevice_depth =
+                        (depth - (depth1 + depth2) / 2.0) * world_units_per_depth / world_radius;
+                    if crevice_depth > MIN_CREVICE_DEPTH && crevice_depth < 2.0 {
+                        occlusion += crevice_depth.min(2.0 - crevice_depth).min(1.0);
+                    }
+                }
+                if pair_count > 0 {
+                    let darkening = 1.0 - self.strength * occlusion / pair_count as f32;
+                    let brightness = image_buffer.get(x, y).unwrap();
+                    image_buffer.set(x, y, brightness * darkening).unwrap();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AmbientOcclusion;
+    use crate::general::positions_3d::Point as Point3;
+    use crate::renderer::interface::Buffer;
+    use crate::renderer::pipeline::transformation::Camera;
+
+    fn camera() -> Camera {
+        Camera {
+            horizontal_fov: 90.0,
+            vertical_fov: 60.0,
+            position: Point3::new(),
+            rotation_around_x: 0.0,
+            rotation_around_y: 0.0,
+            near: 1.0,
+            far: 10.0,
+        }
+    }
+
+    /// Applies ambient occlusion to a white image with the depth given for each column
+    fn occluded_row(depth: impl Fn(usize) -> f32) -> Vec<f32> {
+        let (width, height) = (40, 20);
+        let mut depth_buffer = Buffer::new(width, height, f32::MAX);
+        for y in 0..height {
+            for x in 0..width {
+                depth_buffer.set(x, y, depth(x)).unwrap();
+            }
+        }
+        let mut image_buffer = Buffer::new(width, height, 1.0);
+        AmbientOcclusion::default().apply(&mut image_buffer, &depth_buffer, &camera(), 1);
+        image_buffer.values[10 * width..11 * width].to_vec()
+    }
+
+    #[test]
+    fn test_only_crevices_are_darkened() {
+        // a plane tilted away from the camera
+        let tilted_plane = occluded_row(|x| 0.5 + 0.002 * x as f32);
+        assert!(tilted_plane.iter().all(|&brightness| brightness == 1.0));
+
+        // two planes meeting in a crevice in the middle of the image
+        let crevice = occluded_row(|x| 0.6 - 0.02 * (x as f32 - 20.0).abs());
+        assert!(crevice[20] < 0.8);
+        assert!(crevice[20] < crevice[19] && crevice[19] <= crevice[10]);
+        assert_eq!(crevice[5], 1.0);
+
+        // a ridge is not occluded
+        let ridge = occluded_row(|x| 0.6 + 0.02 * (x as f32 - 20.0).abs());
+        assert!(ridge.iter().all(|&brightness| brightness == 1.0));
+    }
+
+    #[test]
+    fn test_surfaces_far_in_front_do_not_occlude() {
+        // a surface in front of the right half of the image
+        let row = occluded_row(|x| if x < 20 { 0.9 } else { 0.2 });
+        assert!(row.iter().all(|&brightness| brightness == 1.0));
+
+        // pixels without a surface are left as they are
+        let mut image_buffer = Buffer::new(4, 4, 0.5);
+        let depth_buffer = Buffer::new(4, 4, f32::MAX);
+        AmbientOcclusion::default().apply(&mut image_buffer, &depth_buffer, &camera(), 2);
+        assert!(image_buffer
+            .values
+            .iter()
+            .all(|&brightness| brightness == 0.5));
+    }
+}
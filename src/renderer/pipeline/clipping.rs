@@ -0,0 +1,115 @@
+use crate::general::positions_3d::Point as Point3;
+
+/// A triangle corner in homogeneous clip space, with the values that are interpolated across the triangle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipVertex {
+    pub position: [f32; 4],
+    pub world_position: [f32; 3],
+    /// Vertex normal in world space
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: f32,
+    /// Result of the shader at this corner, for Gouraud shading
+    pub brightness: f32,
+}
+
+impl ClipVertex {
+    /// Points in front of the near plane have a depth of at least 0,
+    /// since the perspective projection maps the near plane to 0 and the far plane to w
+    pub fn is_in_front_of_near_plane(&self) -> bool {
+        self.position[2] >= 0.0
+    }
+
+    /// Divides by w, only valid for points in front of the near plane
+    pub fn to_point(&self) -> Point3 {
+        let [x, y, z, w] = self.position;
+        Point3::from_array([x / w, y / w, z / w])
+    }
+
+    fn interpolate(&self, other: &ClipVertex, t: f32) -> ClipVertex {
+        ClipVertex {
+            position: interpolate(self.position, other.position, t),
+            world_position: interpolate(self.world_position, other.world_position, t),
+            normal: interpolate(self.normal, other.normal, t),
+            uv: interpolate(self.uv, other.uv, t),
+            color: interpolate([self.color], [other.color], t)[0],
+            brightness: interpolate([self.brightness], [other.brightness], t)[0],
+        }
+    }
+}
+
+fn interpolate<const N: usize>(start: [f32; N], end: [f32; N], t: f32) -> [f32; N] {
+    std::array::from_fn(|i| start[i] + (end[i] - start[i]) * t)
+}
+
+/// Cuts off the part of the triangle behind the near plane, which would be projected through
+/// the camera and come out mirrored on the other side.
+///
+/// Returns no triangles if the triangle is completely behind the plane, one if one corner is cut off
+/// and two if two corners are cut off. The winding order stays the same.
+pub fn clip_triangle_to_near_plane(triangle: [ClipVertex; 3]) -> Vec<[ClipVertex; 3]> {
+    // Sutherland–Hodgman with a single plane
+    let mut polygon = Vec::with_capacity(4);
+    for corner in 0..3 {
+        let current = &triangle[corner];
+        let next = &triangle[(corner + 1) % 3];
+        if current.is_in_front_of_near_plane() {
+            polygon.push(*current);
+        }
+        if current.is_in_front_of_near_plane() != next.is_in_front_of_near_plane() {
+            let t = current.position[2] / (current.position[2] - next.position[2]);
+            polygon.push(current.interpolate(next, t));
+        }
+    }
+    // a fan around the first corner
+    (2..polygon.len())
+        .map(|i| [polygon[0], polygon[i - 1], polygon[i]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clip_triangle_to_near_plane, ClipVertex};
+
+    fn vertex(z: f32, brightness: f32) -> ClipVertex {
+        ClipVertex {
+            position: [brightness, 0.0, z, 1.0],
+            world_position: [0.0, 0.0, brightness],
+            normal: [0.0, brightness, 1.0],
+            uv: [brightness, 0.0],
+            color: brightness,
+            brightness,
+        }
+    }
+
+    #[test]
+    fn test_triangles_on_one_side_are_kept_or_removed() {
+        let in_front = [vertex(1.0, 0.0), vertex(2.0, 1.0), vertex(0.0, 2.0)];
+        assert_eq!(clip_triangle_to_near_plane(in_front), vec![in_front]);
+        let behind = [vertex(-1.0, 0.0), vertex(-2.0, 1.0), vertex(-0.5, 2.0)];
+        assert!(clip_triangle_to_near_plane(behind).is_empty());
+    }
+
+    #[test]
+    fn test_clipping_interpolates_the_corners() {
+        // one corner behind the plane, its edges are cut off at a quarter and at half of their length
+        let triangle = [vertex(-1.0, 0.0), vertex(3.0, 4.0), vertex(1.0, 8.0)];
+        let clipped = clip_triangle_to_near_plane(triangle);
+        assert_eq!(clipped.len(), 2);
+        assert_eq!(
+            clipped[0],
+            [vertex(0.0, 1.0), vertex(3.0, 4.0), vertex(1.0, 8.0)]
+        );
+        assert_eq!(
+            clipped[1],
+            [vertex(0.0, 1.0), vertex(1.0, 8.0), vertex(0.0, 4.0)]
+        );
+
+        // two corners behind the plane
+        let triangle = [vertex(1.0, 0.0), vertex(-1.0, 2.0), vertex(-3.0, 4.0)];
+        assert_eq!(
+            clip_triangle_to_near_plane(triangle),
+            vec![[vertex(1.0, 0.0), vertex(0.0, 1.0), vertex(0.0, 1.0)]]
+        );
+    }
+}
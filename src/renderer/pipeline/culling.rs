@@ -0,0 +1,210 @@
+use super::transformation::{multiply_points_with_matrix_homogeneous_into, Matrix4x4};
+use crate::general::positions_3d::{Mesh, Point as Point3};
+
+/// The visible volume in the homogeneous pixel space that `render_mesh` transforms points to,
+/// before the perspective divide. Visible points have `0 <= x <= width * w`, `0 <= y <= height * w`
+/// and `0 <= z <= w`, where depth 0 is the near plane and depth 1 the far plane.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewFrustum {
+    width: f32,
+    height: f32,
+}
+
+impl ViewFrustum {
+    pub fn new(width: usize, height: usize) -> Self {
+        ViewFrustum {
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    /// True if all points are outside of the same side of the frustum, so nothing between them is visible.
+    /// Points that are outside of different sides are not excluded, since the shape between them may still cross the frustum.
+    pub fn excludes(&self, points: &[[f32; 4]]) -> bool {
+        points
+            .iter()
+            .fold(u8::MAX, |outcode, point| outcode & self.outcode(point))
+            != 0
+    }
+
+    /// One bit for each side of the frustum that the point is outside of
+    fn outcode(&self, [x, y, z, w]: &[f32; 4]) -> u8 {
+        [
+            *x < 0.0,
+            *x > self.width * w,
+            *y < 0.0,
+            *y > self.height * w,
+            *z < 0.0,
+            z > w,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |outcode, (bit, is_outside)| {
+            outcode | (u8::from(*is_outside) << bit)
+        })
+    }
+}
+
+struct CullingGroup {
+    /// Every point that the triangles use, once
+    points: Vec<u32>,
+    triangles: Vec<u32>,
+}
+
+/// The triangles of each group of a mesh, so that groups outside of the view can be skipped
+/// at once by testing their bounding box, instead of testing each of their triangles.
+///
+/// Only the triangles and their groups are stored, so moving the points of the mesh is fine,
+/// but the culling has to be built again when the triangles change.
+pub struct GroupCulling {
+    groups: Vec<CullingGroup>,
+    point_count: usize,
+    triangle_count: usize,
+}
+
+impl GroupCulling {
+    /// `None` if the mesh has less than two groups, since the whole mesh is rarely outside of the view.
+    /// Triangles without a group are put together in a group of their own.
+    pub fn new(mesh: &Mesh) -> Option<Self> {
+        if mesh.groups.len() < 2 {
+            return None;
+        }
+        let mut triangles_per_group = vec![Vec::new(); mesh.groups.len() + 1];
+        for triangle in 0..mesh.triangle_count() {
+            let group = mesh.group(triangle).unwrap_or(mesh.groups.len());
+            triangles_per_group[group].push(triangle as u32);
+        }
+        let mut is_used = vec![false; mesh.points.len()];
+        let groups = triangles_per_group
+            .into_iter()
+            .filter(|triangles| !triangles.is_empty())
+            .map(|triangles| {
+                let mut points = Vec::new();
+                for &triangle in &triangles {
+                    for point in mesh.triangles[triangle as usize] {
+                        if !is_used[point as usize] {
+                            is_used[point as usize] = true;
+                            points.push(point);
+                        }
+                    }
+                }
+                // points that are shared with later groups are added to those as well
+                for &point in &points {
+                    is_used[point as usize] = false;
+                }
+                CullingGroup { points, triangles }
+            })
+            .collect();
+        Some(GroupCulling {
+            groups,
+            point_count: mesh.points.len(),
+            triangle_count: mesh.triangle_count(),
+        })
+    }
+
+    /// False if the mesh has a different amount of points or triangles than the one the culling was built for
+    pub fn fits(&self, mesh: &Mesh) -> bool {
+        self.point_count == mesh.points.len() && self.triangle_count == mesh.triangle_count()
+    }
+
+    /// The triangles of all groups whose bounding box is at least partly inside the frustum.
+    /// `matrix` transforms world space points to the space of the frustum.
+    pub fn visible_triangles<'a>(
+        &'a self,
+        points: &'a [Point3],
+        matrix: &Matrix4x4,
+        frustum: &ViewFrustum,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let mut transformed_corners = Vec::with_capacity(8);
+        let visible_groups: Vec<&CullingGroup> = self
+            .groups
+            .iter()
+            .filter(|group| {
+                let corners =
+                    bounding_box_corners(group.points.iter().map(|&point| &points[point as usize]));
+                multiply_points_with_matrix_homogeneous_into(
+                    &corners,
+                    matrix,
+                    &mut transformed_corners,
+                );
+                !frustum.excludes(&transformed_corners)
+            })
+            .collect();
+        visible_groups
+            .into_iter()
+            .flat_map(|group| group.triangles.iter().map(|&triangle| triangle as usize))
+    }
+}
+
+fn bounding_box_corners<'a>(points: impl Iterator<Item = &'a Point3>) -> [Point3; 8] {
+    let (min, max) = points.fold(
+        ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]),
+        |(min, max), point| {
+            let point = point.to_array();
+            (
+                [0, 1, 2].map(|axis| min[axis].min(point[axis])),
+                [0, 1, 2].map(|axis| max[axis].max(point[axis])),
+            )
+        },
+    );
+    [0, 1, 2, 3, 4, 5, 6, 7].map(|corner| {
+        Point3::from_array([0, 1, 2].map(|axis| {
+            if corner & (1 << axis) == 0 {
+                min[axis]
+            } else {
+                max[axis]
+            }
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GroupCulling, ViewFrustum};
+    use crate::general::positions_3d::{Mesh, Point as Point3};
+    use crate::renderer::pipeline::transformation::identity_matrix;
+
+    #[test]
+    fn test_frustum_excludes_points_outside_of_one_side() {
+        let frustum = ViewFrustum::new(100, 50);
+        // left of the view, and above and below it
+        assert!(frustum.excludes(&[[-1.0, 10.0, 0.5, 1.0], [-2.0, -30.0, 0.5, 2.0]]));
+        // behind the far plane
+        assert!(frustum.excludes(&[[10.0, 10.0, 2.1, 2.0], [10.0, 10.0, 1.5, 1.0]]));
+        // left and right of the view, the line between them crosses it
+        assert!(!frustum.excludes(&[[-1.0, 10.0, 0.5, 1.0], [300.0, 10.0, 0.5, 2.0]]));
+        // a point inside the view
+        assert!(!frustum.excludes(&[[99.0, 49.0, 0.5, 1.0]]));
+        // the same point, scaled by w
+        assert!(!frustum.excludes(&[[198.0, 98.0, 1.0, 2.0]]));
+    }
+
+    #[test]
+    fn test_groups_outside_of_the_view_are_skipped() {
+        let mut mesh = Mesh::new();
+        for x in [10.0, 20.0, 200.0, 210.0] {
+            mesh.points.push(Point3::from_array([x, 10.0, 0.5]));
+            mesh.points.push(Point3::from_array([x, 20.0, 0.5]));
+        }
+        mesh.groups = vec![String::from("inside"), String::from("outside")];
+        let normal = Point3::from_array([0.0, 0.0, 1.0]);
+        for (group, start) in [(0, 0), (1, 4)] {
+            let first = mesh.push_triangle([start, start + 2, start + 1], normal.clone());
+            let second = mesh.push_triangle([start + 1, start + 2, start + 3], normal.clone());
+            mesh.set_group(first, group);
+            mesh.set_group(second, group);
+        }
+        mesh.push_triangle([0, 1, 2], normal);
+
+        let culling = GroupCulling::new(&mesh).unwrap();
+        assert!(culling.fits(&mesh));
+        // with the identity matrix the points are already in pixel space, with w = 1
+        let visible_triangles: Vec<usize> = culling
+            .visible_triangles(&mesh.points, &identity_matrix(), &ViewFrustum::new(100, 50))
+            .collect();
+        assert_eq!(visible_triangles, vec![0, 1, 4]);
+
+        mesh.groups.pop();
+        assert!(GroupCulling::new(&mesh).is_none());
+    }
+}
@@ -1,7 +1,258 @@
-use crate::general::positions_3d::Triangle as Triangle3;
+use crate::general::material::Material;
+use crate::general::positions_2d::Point as Point2;
+use crate::general::positions_3d::{dot_product, Point as Point3};
+use crate::renderer::pipeline::light::Light;
+use crate::renderer::pipeline::shadow::ShadowMap;
 
-// TODO barycentric coordinates as a parameter
-pub fn fragment_shader(ss_triangle: &Triangle3, // screen space triangle
-) -> f32 {
-    ss_triangle.normal.x // TODO
+/// The values of a triangle at one pixel, interpolated from its corners
+#[derive(Debug, Clone)]
+pub struct Fragment {
+    /// Position in world space
+    pub position: Point3,
+    /// Unit normal in world space, the face normal with flat shading
+    pub normal: Point3,
+    /// Texture coordinates, if the triangle has them
+    pub uv: Option<Point2>,
+    /// Grayscale color from 0 to 1, if the renderer was given one for every point of the mesh
+    pub color: Option<f32>,
+    /// Index into `Mesh::materials`
+    pub material: Option<usize>,
+    /// 0 at the near plane and 1 at the far plane
+    pub depth: f32,
+}
+
+/// The lights of the scene and the position they are seen from
+#[derive(Debug, Clone)]
+pub struct Lighting {
+    pub lights: Vec<Light>,
+    /// Brightness of surfaces that face away from all lights
+    pub ambient_light: f32,
+    pub camera_position: Point3,
+    /// The shadow map of each light, lights without one are never blocked. Empty without shadows
+    pub shadow_maps: Vec<Option<ShadowMap>>,
+}
+
+impl Lighting {
+    /// The unit vector each light shines along at `position`, and how much of its brightness reaches it
+    pub fn incoming_light<'a>(
+        &'a self,
+        position: &'a Point3,
+    ) -> impl Iterator<Item = (Point3, f32)> + 'a {
+        self.lights.iter().enumerate().map(move |(index, light)| {
+            let (direction, brightness) = light.illuminate(position, &self.camera_position);
+            match self.shadow_maps.get(index) {
+                Some(Some(shadow_map)) if brightness > 0.0 => {
+                    (direction, brightness * shadow_map.visibility(position))
+                }
+                _ => (direction, brightness),
+            }
+        })
+    }
+}
+
+/// Decides how bright the surface of a mesh is at each pixel.
+///
+/// With Gouraud shading the shader is only run for the corners of the triangles,
+/// with the corners as fragments, and the brightness is interpolated between them.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::renderer::pipeline::fragment_shader::{Fragment, Lighting, Shader};
+///
+/// /// Draws the surface darker the further away it is
+/// struct DepthShader;
+///
+/// impl Shader for DepthShader {
+///     fn shade(&self, fragment: &Fragment, _lighting: &Lighting) -> f32 {
+///         1.0 - fragment.depth
+///     }
+/// }
+/// ```
+pub trait Shader {
+    /// Returns a brightness from 0 to 1
+    fn shade(&self, fragment: &Fragment, lighting: &Lighting) -> f32;
+}
+
+/// Lights the surface from its normal, from `Lighting::ambient_light` for surfaces facing away from the lights up to 1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffuseShader;
+
+impl Shader for DiffuseShader {
+    fn shade(&self, fragment: &Fragment, lighting: &Lighting) -> f32 {
+        let light_intensity: f32 = lighting
+            .incoming_light(&fragment.position)
+            .map(|(direction, brightness)| {
+                brightness * diffuse_intensity(&fragment.normal, &direction)
+            })
+            .sum();
+        (light_intensity * (1.0 - lighting.ambient_light) + lighting.ambient_light).clamp(0.0, 1.0)
+    }
+}
+
+/// `DiffuseShader` with view dependent highlights, using the `Ks` and `Ns` of the materials.
+/// Shiny materials like metal get small, bright highlights that move with the camera.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::material::Material;
+/// use rust_obj_terminal_viewer::renderer::pipeline::fragment_shader::BlinnPhongShader;
+///
+/// let mut metal = Material::new("Metal");
+/// metal.specular = Some(0.5);
+/// metal.shininess = Some(225.0);
+/// let shader = BlinnPhongShader::new(&[metal]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlinnPhongShader {
+    /// The materials of the mesh, `Fragment::material` is an index into them
+    materials: Vec<Material>,
+    /// Used for triangles without a material, or if the material does not set the property
+    pub default_specular: f32,
+    pub default_shininess: f32,
+}
+
+impl BlinnPhongShader {
+    pub fn new(materials: &[Material]) -> Self {
+        BlinnPhongShader {
+            materials: materials.to_vec(),
+            default_specular: 0.2,
+            default_shininess: 20.0,
+        }
+    }
+}
+
+impl Default for BlinnPhongShader {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Shader for BlinnPhongShader {
+    fn shade(&self, fragment: &Fragment, lighting: &Lighting) -> f32 {
+        let material = fragment
+            .material
+            .and_then(|material| self.materials.get(material));
+        let property = |value: Option<f32>, default: f32| value.unwrap_or(default);
+        let specular = property(material.and_then(|m| m.specular), self.default_specular);
+        let shininess = property(material.and_then(|m| m.shininess), self.default_shininess);
+
+        let ambient_light = lighting.ambient_light;
+        let to_camera = lighting
+            .camera_position
+            .relative_to(&fragment.position)
+            .normalized();
+        let light_intensity: f32 = lighting
+            .incoming_light(&fragment.position)
+            .map(|(direction, brightness)| {
+                let diffuse_intensity = diffuse_intensity(&fragment.normal, &direction);
+                if diffuse_intensity <= 0.0 {
+                    return 0.0;
+                }
+                // the halfway vector between the directions to the light and to the camera
+                let halfway = direction.inverted().add(&to_camera).normalized();
+                let specular_intensity = dot_product(&fragment.normal, &halfway)
+                    .max(0.0)
+                    .powf(shininess);
+                brightness
+                    * (diffuse_intensity * (1.0 - ambient_light) + specular * specular_intensity)
+            })
+            .sum();
+        (ambient_light + light_intensity).clamp(0.0, 1.0)
+    }
+}
+
+/// Shows `Fragment::color` without lighting, for example the deviations of the `diff` command.
+/// Falls back to `DiffuseShader` for meshes without colors, like simplified versions of the mesh.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VertexColorShader;
+
+impl Shader for VertexColorShader {
+    fn shade(&self, fragment: &Fragment, lighting: &Lighting) -> f32 {
+        match fragment.color {
+            Some(color) => color,
+            None => DiffuseShader.shade(fragment, lighting),
+        }
+    }
+}
+
+/// Assumes that both normal and light direction are unit vectors
+fn diffuse_intensity(normal: &Point3, light_direction: &Point3) -> f32 {
+    dot_product(normal, &light_direction.inverted()).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlinnPhongShader, DiffuseShader, Fragment, Lighting, Shader, VertexColorShader};
+    use crate::general::material::Material;
+    use crate::general::positions_3d::Point as Point3;
+    use crate::renderer::pipeline::light::Light;
+
+    fn fragment(normal: [f32; 3], color: Option<f32>) -> Fragment {
+        Fragment {
+            position: Point3::new(),
+            normal: Point3::from_array(normal),
+            uv: None,
+            color,
+            material: None,
+            depth: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_diffuse_and_vertex_color_shaders() {
+        let lighting = Lighting {
+            lights: vec![Light::directional(Point3::from_array([0.0, -1.0, 0.0]))],
+            ambient_light: 0.2,
+            camera_position: Point3::new(),
+            shadow_maps: Vec::new(),
+        };
+        let facing_light = fragment([0.0, 1.0, 0.0], None);
+        let facing_away = fragment([0.0, -1.0, 0.0], None);
+        assert_eq!(DiffuseShader.shade(&facing_light, &lighting), 1.0);
+        assert_eq!(DiffuseShader.shade(&facing_away, &lighting), 0.2);
+
+        let colored = fragment([0.0, 1.0, 0.0], Some(0.4));
+        assert_eq!(VertexColorShader.shade(&colored, &lighting), 0.4);
+        assert_eq!(VertexColorShader.shade(&facing_away, &lighting), 0.2);
+
+        // every light only brightens the side that it shines on
+        let mut two_lights = lighting.clone();
+        two_lights.lights = vec![
+            Light::directional(Point3::from_array([0.0, -1.0, 0.0])).with_intensity(0.25),
+            Light::directional(Point3::from_array([0.0, 1.0, 0.0])).with_intensity(0.5),
+        ];
+        assert!((DiffuseShader.shade(&facing_light, &two_lights) - 0.4).abs() < 1e-6);
+        assert!((DiffuseShader.shade(&facing_away, &two_lights) - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_highlights_depend_on_the_view_and_the_material() {
+        let mut metal = Material::new("Metal");
+        metal.specular = Some(0.2);
+        metal.shininess = Some(200.0);
+        let shader = BlinnPhongShader::new(&[metal]);
+        // the light comes in at 45 degrees from the left
+        let light = Light::directional(Point3::from_array([1.0, -1.0, 0.0]));
+        let lighting = |camera_position: [f32; 3]| Lighting {
+            lights: vec![light.clone()],
+            ambient_light: 0.1,
+            camera_position: Point3::from_array(camera_position),
+            shadow_maps: Vec::new(),
+        };
+        let mut metal_fragment = fragment([0.0, 1.0, 0.0], None);
+        metal_fragment.material = Some(0);
+
+        let above = lighting([0.0, 10.0, 0.0]);
+        let without_highlight = DiffuseShader.shade(&metal_fragment, &above);
+        // the reflection points into the camera
+        let reflected = lighting([10.0, 10.0, 0.0]);
+        assert!(
+            (shader.shade(&metal_fragment, &reflected) - (without_highlight + 0.2)).abs() < 1e-4
+        );
+        // seen from above, the highlight of a shiny material is gone
+        assert!((shader.shade(&metal_fragment, &above) - without_highlight).abs() < 1e-4);
+        // triangles without a material use the defaults, which give a wider highlight
+        let default_fragment = fragment([0.0, 1.0, 0.0], None);
+        assert!(shader.shade(&default_fragment, &above) > without_highlight + 0.01);
+    }
 }
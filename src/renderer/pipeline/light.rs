@@ -0,0 +1,195 @@
+use crate::general::positions_3d::{dot_product, Point as Point3};
+use std::str::FromStr;
+
+/// Where a light shines from
+#[derive(Debug, Clone, PartialEq)]
+pub enum LightKind {
+    /// Shines along `direction` everywhere, like the sun
+    Directional { direction: Point3 },
+    /// Shines from `position` in all directions, the brightness halves at `range` away from it
+    Point { position: Point3, range: f32 },
+    /// Shines from the camera towards what it looks at, so the visible side of the model is always lit
+    Headlight,
+}
+
+/// A light source of the scene.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::positions_3d::Point as Point3;
+/// use rust_obj_terminal_viewer::renderer::pipeline::light::Light;
+///
+/// // three-point lighting: a bright key light, a weaker fill light from the other side and a rim light from behind
+/// let lights = vec![
+///     Light::directional(Point3::from_array([1.0, -1.0, -1.0])),
+///     Light::directional(Point3::from_array([-1.0, -0.5, -1.0])).with_intensity(0.4),
+///     Light::directional(Point3::from_array([0.0, -1.0, 1.0])).with_intensity(0.6),
+/// ];
+/// let headlight: Light = "headlight:0.5".parse().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub intensity: f32,
+    /// Red, green and blue from 0 to 1, the terminal only shows their average brightness
+    pub color: Option<[f32; 3]>,
+}
+
+impl Light {
+    /// `direction` does not have to be a unit vector
+    pub fn directional(direction: Point3) -> Self {
+        Light::new(LightKind::Directional {
+            direction: direction.normalized(),
+        })
+    }
+
+    pub fn point(position: Point3, range: f32) -> Self {
+        Light::new(LightKind::Point { position, range })
+    }
+
+    pub fn headlight() -> Self {
+        Light::new(LightKind::Headlight)
+    }
+
+    fn new(kind: LightKind) -> Self {
+        Light {
+            kind,
+            intensity: 1.0,
+            color: None,
+        }
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// The unit vector the light shines along at `position`, and how bright it is there
+    pub fn illuminate(&self, position: &Point3, camera_position: &Point3) -> (Point3, f32) {
+        let color_brightness = self
+            .color
+            .map_or(1.0, |[red, green, blue]| (red + green + blue) / 3.0);
+        let brightness = self.intensity * color_brightness;
+        match &self.kind {
+            LightKind::Directional { direction } => (direction.clone(), brightness),
+            LightKind::Point {
+                position: light_position,
+                range,
+            } => {
+                let offset = position.relative_to(light_position);
+                let squared_distance = dot_product(&offset, &offset);
+                let falloff = 1.0 / (1.0 + squared_distance / (range * range));
+                (offset.normalized(), brightness * falloff)
+            }
+            LightKind::Headlight => (
+                position.relative_to(camera_position).normalized(),
+                brightness,
+            ),
+        }
+    }
+}
+
+impl FromStr for Light {
+    type Err = String;
+
+    /// Parses strings like "directional:-1,-1,-1", "point:0,5,0:10:0.8" and "headlight:0.5:1,0.9,0.8".
+    /// Directional lights take the direction they shine along, point lights their position and range.
+    /// They are followed by an optional intensity and color.
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let mut fields = string.split(':');
+        let kind = fields.next().unwrap_or_default().to_lowercase();
+        let mut light = match kind.as_str() {
+            "directional" => Light::directional(Point3::from_array(parse_numbers(fields.next())?)),
+            "point" => {
+                let position = Point3::from_array(parse_numbers(fields.next())?);
+                let [range] = parse_numbers(fields.next())?;
+                Light::point(position, range)
+            }
+            "headlight" => Light::headlight(),
+            _ => {
+                return Err(format!(
+                    "'{kind}' is not a light type, use directional, point or headlight"
+                ))
+            }
+        };
+        if let Some(intensity) = fields.next() {
+            let [intensity] = parse_numbers(Some(intensity))?;
+            light = light.with_intensity(intensity);
+        }
+        if let Some(color) = fields.next() {
+            light = light.with_color(parse_numbers(Some(color))?);
+        }
+        if fields.next().is_some() {
+            return Err(format!("'{string}' has too many fields"));
+        }
+        Ok(light)
+    }
+}
+
+fn parse_numbers<const N: usize>(field: Option<&str>) -> Result<[f32; N], String> {
+    let field = field.ok_or("a light field is missing")?;
+    let numbers = field
+        .split(',')
+        .map(|number| number.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| format!("'{field}' is not a list of numbers"))?;
+    numbers
+        .try_into()
+        .map_err(|_| format!("'{field}' should have {N} numbers"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Light, LightKind};
+    use crate::general::positions_3d::Point as Point3;
+
+    #[test]
+    fn test_lights_are_parsed() {
+        let light: Light = "directional:0,-2,0".parse().unwrap();
+        assert_eq!(
+            light,
+            Light::directional(Point3::from_array([0.0, -1.0, 0.0]))
+        );
+
+        let light: Light = "point:1,2,3:10:0.5:1,0,0.5".parse().unwrap();
+        assert_eq!(
+            light.kind,
+            LightKind::Point {
+                position: Point3::from_array([1.0, 2.0, 3.0]),
+                range: 10.0
+            }
+        );
+        assert_eq!(light.intensity, 0.5);
+        assert_eq!(light.color, Some([1.0, 0.0, 0.5]));
+
+        assert_eq!("Headlight".parse(), Ok(Light::headlight()));
+        assert!("point:1,2,3".parse::<Light>().is_err());
+        assert!("spot:1,2,3".parse::<Light>().is_err());
+        assert!("headlight:1:1,1,1:2".parse::<Light>().is_err());
+    }
+
+    #[test]
+    fn test_point_lights_fall_off_with_distance() {
+        let light = Light::point(Point3::from_array([0.0, 10.0, 0.0]), 5.0).with_intensity(2.0);
+        let camera_position = Point3::new();
+        let (direction, brightness) = light.illuminate(&Point3::new(), &camera_position);
+        assert_eq!(direction, Point3::from_array([0.0, -1.0, 0.0]));
+        // twice the range away
+        assert!((brightness - 2.0 / 5.0).abs() < 1e-6);
+
+        let (_, brightness) =
+            light.illuminate(&Point3::from_array([0.0, 5.0, 0.0]), &camera_position);
+        assert!((brightness - 1.0).abs() < 1e-6);
+
+        let headlight = Light::headlight().with_color([1.0, 0.5, 0.0]);
+        let (direction, brightness) =
+            headlight.illuminate(&Point3::from_array([0.0, 0.0, -4.0]), &camera_position);
+        assert_eq!(direction, Point3::from_array([0.0, 0.0, -1.0]));
+        assert_eq!(brightness, 0.5);
+    }
+}
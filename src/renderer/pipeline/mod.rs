@@ -1,4 +1,9 @@
+pub mod ambient_occlusion;
+pub mod clipping;
+pub mod culling;
 pub mod fragment_shader;
+pub mod light;
 pub mod rasterization;
+pub mod shadow;
 pub mod terminal_output;
 pub mod transformation;
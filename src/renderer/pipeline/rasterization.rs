@@ -1,18 +1,21 @@
 use std::cmp::min;
 
 use crate::general::positions_2d::{Point as Point2, Triangle as Triangle2};
-use crate::general::positions_3d::Triangle as Triangle3;
+use crate::general::positions_3d::{Point as Point3, Triangle as Triangle3};
 use crate::renderer::interface::Buffer;
-use crate::renderer::pipeline::fragment_shader::fragment_shader;
 
-pub fn render_triangle(
+/// `shade` returns the brightness of a pixel from its barycentric coordinates,
+/// the weights of the three corners of the triangle
+pub fn render_triangle<F>(
     ps_triangle: &Triangle3, // pixel space triangle
     // TODO ^ function should take screen space triangle (normalized screen coordinates) instead
     // Then you can check screen space intersection and render with the same triangle
     pixel_buffer: &mut Buffer<f32>,
     depth_buffer: &mut Buffer<f32>,
-    light_intensity: Option<f32>,
-) {
+    shade: F,
+) where
+    F: Fn([f32; 3]) -> f32,
+{
     let triangle2 = ps_triangle.to_2d();
     if !triangle2.has_area() {
         return;
@@ -49,18 +52,53 @@ pub fn render_triangle(
             if frag_depth <= depth_buffer_value {
                 depth_buffer.set(x, y, frag_depth).unwrap();
                 // TODO triangle should be screen space (-1 to 1), is currently (-width*0.5 to width*0.5)
-                if let Some(light_intensity) = light_intensity {
-                    pixel_buffer.set(x, y, light_intensity).unwrap();
-                } else {
-                    pixel_buffer
-                        .set(x, y, fragment_shader(ps_triangle))
-                        .unwrap();
-                }
+                pixel_buffer.set(x, y, shade([w, u, v])).unwrap();
             }
         }
     }
 }
 
+/// Draws a line between two pixel space points, without depth testing
+pub fn render_line<T: Copy>(p1: &Point3, p2: &Point3, buffer: &mut Buffer<T>, value: T) {
+    let (dx, dy) = (p2.x - p1.x, p2.y - p1.y);
+    let (width, height) = (buffer.width as f32, buffer.height as f32);
+
+    // clip the line to the buffer (Liang-Barsky), so that long lines are fast to draw
+    let (mut t_start, mut t_end) = (0.0_f32, 1.0_f32);
+    for (p, q) in [
+        (-dx, p1.x),
+        (dx, width - p1.x),
+        (-dy, p1.y),
+        (dy, height - p1.y),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return;
+            }
+            continue;
+        }
+        let t = q / p;
+        if p < 0.0 {
+            t_start = t_start.max(t);
+        } else {
+            t_end = t_end.min(t);
+        }
+    }
+    if t_start > t_end {
+        return;
+    }
+
+    let steps = (dx.abs().max(dy.abs()) * (t_end - t_start)).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = t_start + (t_end - t_start) * step as f32 / steps as f32;
+        let (x, y) = ((p1.x + dx * t).round(), (p1.y + dy * t).round());
+        if x >= 0.0 && y >= 0.0 {
+            // points on the right and bottom border are outside of the buffer
+            let _ = buffer.set(x as usize, y as usize, value);
+        }
+    }
+}
+
 /// `P = v0 * w0 + v1 * w1 + v2 * w2` where
 /// v0, v1, v2 are vertices of triangle, P is point,
 /// and \[w0, w1, w2\] the return value of this function
@@ -94,7 +132,10 @@ fn edge_function(p1: &Point2, p2: &Point2) -> f32 {
 mod test_bc {
     use crate::general::positions_2d::Point as Point2;
     use crate::general::positions_2d::Triangle as Triangle2;
+    use crate::general::positions_3d::Point as Point3;
+    use crate::renderer::interface::Buffer;
     use crate::renderer::pipeline::rasterization::get_barycentric_coordinates;
+    use crate::renderer::pipeline::rasterization::render_line;
 
     /// bcc => BaryCentric Coordinates
     fn get_point_from_bcc(triangle: &Triangle2, point: &Point2) -> Point2 {
@@ -127,4 +168,13 @@ mod test_bc {
         let point = Point2 { x: 1.0, y: 0.5 };
         assert_eq!(point, get_point_from_bcc(&triangle, &point));
     }
+
+    #[test]
+    fn test_render_line_is_clipped_to_buffer() {
+        let mut buffer = Buffer::new(4, 3, 0);
+        let p1 = Point3::from_array([-100.0, 1.0, 0.5]);
+        let p2 = Point3::from_array([100.0, 1.0, 0.5]);
+        render_line(&p1, &p2, &mut buffer, 1);
+        assert_eq!(buffer.values, vec![0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0]);
+    }
 }
@@ -0,0 +1,195 @@
+use crate::general::positions_3d::{
+    cross_product, dot_product, BoundingBox, Mesh, Point as Point3, Triangle as Triangle3,
+};
+use crate::renderer::interface::Buffer;
+use crate::renderer::pipeline::light::{Light, LightKind};
+use crate::renderer::pipeline::rasterization::render_triangle;
+
+/// Depth of the mesh as seen from a directional light, to find the parts of the surface that are in its shadow.
+///
+/// # Example
+/// ```
+/// use rust_obj_terminal_viewer::general::positions_3d::{Mesh, Point as Point3};
+/// use rust_obj_terminal_viewer::renderer::pipeline::light::Light;
+/// use rust_obj_terminal_viewer::renderer::pipeline::shadow::ShadowMap;
+///
+/// let mut mesh = Mesh::new();
+/// mesh.points = vec![
+///     Point3::from_array([-1.0, 0.0, -1.0]),
+///     Point3::from_array([1.0, 0.0, -1.0]),
+///     Point3::from_array([0.0, 0.0, 1.0]),
+/// ];
+/// mesh.push_triangle([0, 1, 2], Point3::from_array([0.0, 1.0, 0.0]));
+///
+/// let sun = Light::directional(Point3::from_array([0.0, -1.0, 0.0]));
+/// let shadow_map = ShadowMap::new(&mesh, &sun, 64).unwrap();
+/// assert_eq!(shadow_map.visibility(&Point3::from_array([0.0, 1.0, 0.0])), 1.0);
+/// assert_eq!(shadow_map.visibility(&Point3::from_array([0.0, -1.0, 0.0])), 0.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShadowMap {
+    /// Distance along the light direction from `center` of the surface closest to the light, for each texel
+    depth_buffer: Buffer<f32>,
+    center: Point3,
+    radius: f32,
+    /// Unit vectors across the map and along the light direction
+    right: Point3,
+    up: Point3,
+    direction: Point3,
+    /// In world units, surfaces are only shadowed by surfaces at least this much closer to the light.
+    /// Prevents surfaces from shadowing themselves, but detaches shadows from their casters if too large
+    pub bias: f32,
+    /// Averages the texels within this distance for soft shadow edges, 0 gives hard edges
+    pub pcf_radius: usize,
+}
+
+impl ShadowMap {
+    /// Renders the mesh from the light into a map of `size` × `size` texels, which covers the bounding sphere of the mesh.
+    /// Only directional lights cast shadows, returns None for other lights and for empty meshes
+    pub fn new(mesh: &Mesh, light: &Light, size: usize) -> Option<Self> {
+        let LightKind::Directional { direction } = &light.kind else {
+            return None;
+        };
+        if mesh.points.is_empty() || size == 0 {
+            return None;
+        }
+        let bounding_box = BoundingBox::new(&mesh.points);
+        let center = bounding_box.get_center();
+        let radius = bounding_box.get_bounding_radius().max(f32::EPSILON);
+        // any axis that is not parallel to the light works for the orientation of the map
+        let helper_axis = if direction.y.abs() < 0.9 {
+            Point3::from_array([0.0, 1.0, 0.0])
+        } else {
+            Point3::from_array([1.0, 0.0, 0.0])
+        };
+        let right = cross_product(helper_axis, direction.clone()).normalized();
+        let up = cross_product(direction.clone(), right.clone());
+        let texel_size = 2.0 * radius / size as f32;
+
+        let mut shadow_map = ShadowMap {
+            depth_buffer: Buffer::new(size, size, f32::MAX),
+            center,
+            radius,
+            right,
+            up,
+            direction: direction.clone(),
+            bias: 2.0 * texel_size,
+            pcf_radius: 1,
+        };
+        let map_points: Vec<Point3> = mesh
+            .points
+            .iter()
+            .map(|point| shadow_map.to_map_space(point))
+            .collect();
+        // only the depth is needed, but the rasterizer also writes the brightness
+        let mut unused_image_buffer = Buffer::new(size, size, 0.0);
+        // both sides of the triangles are rendered, so that open meshes cast shadows as well
+        for (indices, normal) in mesh.triangles.iter().zip(&mesh.face_normals) {
+            let Some(triangle) = Triangle3::from_indices(indices, normal, &map_points) else {
+                continue;
+            };
+            render_triangle(
+                &triangle,
+                &mut unused_image_buffer,
+                &mut shadow_map.depth_buffer,
+                |_| 0.0,
+            );
+        }
+        Some(shadow_map)
+    }
+
+    /// How much of the light reaches a position in world space, from 0 in the shadow to 1 in the light
+    pub fn visibility(&self, position: &Point3) -> f32 {
+        let map_point = self.to_map_space(position);
+        let [x, y] = [map_point.x, map_point.y].map(|coordinate| coordinate.round() as isize);
+        let radius = self.pcf_radius as isize;
+        let mut lit_texels = 0;
+        let mut texels = 0;
+        for texel_y in y - radius..=y + radius {
+            for texel_x in x - radius..=x + radius {
+                texels += 1;
+                // nothing outside of the map blocks the light
+                let occluder_depth = usize::try_from(texel_x)
+                    .ok()
+                    .zip(usize::try_from(texel_y).ok())
+                    .and_then(|(texel_x, texel_y)| self.depth_buffer.get(texel_x, texel_y))
+                    .unwrap_or(f32::MAX);
+                if map_point.z - self.bias <= occluder_depth {
+                    lit_texels += 1;
+                }
+            }
+        }
+        lit_texels as f32 / texels as f32
+    }
+
+    /// x and y are texel coordinates, z is the distance along the light direction from the center of the mesh
+    fn to_map_space(&self, point: &Point3) -> Point3 {
+        let offset = point.relative_to(&self.center);
+        let size = self.depth_buffer.width as f32;
+        let to_texels = |coordinate: f32| (coordinate / self.radius + 1.0) * 0.5 * size;
+        Point3::from_array([
+            to_texels(dot_product(&offset, &self.right)),
+            to_texels(-dot_product(&offset, &self.up)),
+            dot_product(&offset, &self.direction),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShadowMap;
+    use crate::general::positions_3d::{Mesh, Point as Point3};
+    use crate::renderer::pipeline::light::Light;
+
+    /// A small square above a large one, both facing up
+    fn two_floors() -> Mesh {
+        let mut mesh = Mesh::new();
+        for (half_size, height) in [(4.0, 0.0), (1.0, 2.0)] {
+            let first = mesh.points.len();
+            mesh.points.extend(
+                [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+                    .map(|[x, z]| Point3::from_array([x * half_size, height, z * half_size])),
+            );
+            let up = Point3::from_array([0.0, 1.0, 0.0]);
+            mesh.push_triangle([first, first + 1, first + 2], up.clone());
+            mesh.push_triangle([first, first + 2, first + 3], up);
+        }
+        mesh
+    }
+
+    #[test]
+    fn test_upper_floor_shadows_the_lower_one() {
+        let mesh = two_floors();
+        let sun = Light::directional(Point3::from_array([0.0, -1.0, 0.0]));
+        let mut shadow_map = ShadowMap::new(&mesh, &sun, 128).unwrap();
+        shadow_map.pcf_radius = 0;
+        let below_upper_floor = Point3::from_array([0.0, 0.0, 0.0]);
+        let beside_upper_floor = Point3::from_array([3.0, 0.0, 0.0]);
+        // the surfaces closest to the light do not shadow themselves
+        assert_eq!(
+            shadow_map.visibility(&Point3::from_array([0.5, 2.0, 0.5])),
+            1.0
+        );
+        assert_eq!(shadow_map.visibility(&beside_upper_floor), 1.0);
+        assert_eq!(shadow_map.visibility(&below_upper_floor), 0.0);
+
+        // from the side the upper floor casts no shadow on the lower one
+        let side_light = Light::directional(Point3::from_array([1.0, 0.0, 0.0]));
+        let shadow_map = ShadowMap::new(&mesh, &side_light, 128).unwrap();
+        assert_eq!(shadow_map.visibility(&below_upper_floor), 1.0);
+
+        assert!(ShadowMap::new(&mesh, &Light::headlight(), 128).is_none());
+    }
+
+    #[test]
+    fn test_pcf_softens_the_shadow_edge() {
+        let mesh = two_floors();
+        let sun = Light::directional(Point3::from_array([0.0, -1.0, 0.0]));
+        let mut shadow_map = ShadowMap::new(&mesh, &sun, 64).unwrap();
+        shadow_map.pcf_radius = 2;
+        // the edge of the upper floor
+        let visibility = shadow_map.visibility(&Point3::from_array([1.0, 0.0, 0.0]));
+        assert!(visibility > 0.0 && visibility < 1.0);
+        assert_eq!(shadow_map.visibility(&Point3::new()), 0.0);
+    }
+}
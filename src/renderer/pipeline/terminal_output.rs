@@ -45,6 +45,28 @@ pub fn image_buffer_to_char_buffer(
     }
 }
 
+/// Averages blocks of `factor` × `factor` values of `image_buffer` into one value of `output` (a box filter).
+/// `image_buffer` has to be `factor` times the size of `output`.
+pub fn downsample_image_buffer(
+    image_buffer: &Buffer<f32>,
+    factor: usize,
+    output: &mut Buffer<f32>,
+) {
+    let sample_count = (factor * factor) as f32;
+    for y in 0..(output.height) {
+        for x in 0..(output.width) {
+            let mut sum = 0.0;
+            for sample_y in (y * factor)..((y + 1) * factor) {
+                let row_start = sample_y * image_buffer.width + x * factor;
+                sum += image_buffer.values[row_start..(row_start + factor)]
+                    .iter()
+                    .sum::<f32>();
+            }
+            output.set(x, y, sum / sample_count).unwrap();
+        }
+    }
+}
+
 pub fn add_info_line_to_char_buffer(char_buffer: &mut Buffer<u8>, line: &str) {
     let mut chars: Vec<u8> = line.as_bytes().to_vec();
     chars.resize(char_buffer.width, b' ');
@@ -54,3 +76,21 @@ pub fn add_info_line_to_char_buffer(char_buffer: &mut Buffer<u8>, line: &str) {
         char_buffer.set(x, y, chars[x]).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::downsample_image_buffer;
+    use crate::renderer::interface::Buffer;
+
+    #[test]
+    fn test_downsampling_averages_blocks() {
+        let mut image_buffer = Buffer::new(4, 2, 0.0);
+        image_buffer.values = vec![
+            1.0, 1.0, 0.0, 1.0, //
+            1.0, 1.0, 0.0, 0.0,
+        ];
+        let mut output = Buffer::new(2, 1, 0.0);
+        downsample_image_buffer(&image_buffer, 2, &mut output);
+        assert_eq!(output.values, vec![1.0, 0.25]);
+    }
+}
@@ -1,12 +1,14 @@
 use crate::general::positions_3d::Point as Point3;
-use crate::general::positions_3d::Triangle as Triangle3;
 
-type Matrix4x1 = [[f32; 1]; 4];
-type Matrix4x4 = [[f32; 4]; 4];
+pub type Matrix4x1 = [[f32; 1]; 4];
+pub type Matrix4x4 = [[f32; 4]; 4];
 
 pub trait MatrixTrait {
     fn multiply(&self, _: Matrix4x1) -> Matrix4x1;
     fn combine(&self, _: Matrix4x4) -> Matrix4x4;
+    fn transpose(&self) -> Matrix4x4;
+    /// Returns None if the matrix is not invertible
+    fn inverse(&self) -> Option<Matrix4x4>;
 }
 
 impl Point3 {
@@ -56,6 +58,56 @@ impl MatrixTrait for Matrix4x4 {
         }
         new_matrix
     }
+
+    fn transpose(&self) -> Matrix4x4 {
+        let mut new_matrix = *self;
+        for row_i in 0..4 {
+            for col_i in 0..4 {
+                new_matrix[row_i][col_i] = self[col_i][row_i];
+            }
+        }
+        new_matrix
+    }
+
+    /// Gauss-Jordan elimination with partial pivoting
+    fn inverse(&self) -> Option<Matrix4x4> {
+        let mut matrix = *self;
+        let mut inverse = identity_matrix();
+        // relative to the scale of the matrix, so that very small or large scales can be inverted.
+        // The translation column is left out, a large translation does not make a matrix singular
+        let largest_entry = self[..3]
+            .iter()
+            .flat_map(|row| &row[..3])
+            .fold(0.0_f32, |max, value| max.max(value.abs()));
+        let tolerance = largest_entry * f32::EPSILON;
+        for col_i in 0..4 {
+            let pivot_row_i = (col_i..4)
+                .max_by(|a, b| matrix[*a][col_i].abs().total_cmp(&matrix[*b][col_i].abs()))
+                .unwrap();
+            if matrix[pivot_row_i][col_i].abs() <= tolerance {
+                return None;
+            }
+            matrix.swap(col_i, pivot_row_i);
+            inverse.swap(col_i, pivot_row_i);
+
+            let pivot = matrix[col_i][col_i];
+            for i in 0..4 {
+                matrix[col_i][i] /= pivot;
+                inverse[col_i][i] /= pivot;
+            }
+            for row_i in 0..4 {
+                let factor = matrix[row_i][col_i];
+                if row_i == col_i || factor == 0.0 {
+                    continue;
+                }
+                for i in 0..4 {
+                    matrix[row_i][i] -= factor * matrix[col_i][i];
+                    inverse[row_i][i] -= factor * inverse[col_i][i];
+                }
+            }
+        }
+        Some(inverse)
+    }
 }
 
 impl MatrixVector for Matrix4x1 {
@@ -93,6 +145,48 @@ pub fn rotation_matrix_y(angle: f32) -> Matrix4x4 {
     ]
 }
 
+/// Rotates around an axis through the origin, counterclockwise when looking against the axis direction.
+/// The axis does not need to be normalized.
+pub fn rotation_matrix_axis(axis: &Point3, angle: f32) -> Matrix4x4 {
+    let Point3 { x, y, z } = axis.normalized();
+    let (sin, cos) = angle.sin_cos();
+    let t = 1. - cos;
+    [
+        [
+            t * x * x + cos,
+            t * x * y - sin * z,
+            t * x * z + sin * y,
+            0.,
+        ],
+        [
+            t * x * y + sin * z,
+            t * y * y + cos,
+            t * y * z - sin * x,
+            0.,
+        ],
+        [
+            t * x * z - sin * y,
+            t * y * z + sin * x,
+            t * z * z + cos,
+            0.,
+        ],
+        [0., 0., 0., 1.],
+    ]
+}
+
+pub fn scale_matrix(x: f32, y: f32, z: f32) -> Matrix4x4 {
+    [
+        [x, 0., 0., 0.],
+        [0., y, 0., 0.],
+        [0., 0., z, 0.],
+        [0., 0., 0., 1.],
+    ]
+}
+
+pub fn identity_matrix() -> Matrix4x4 {
+    scale_matrix(1., 1., 1.)
+}
+
 pub fn translation_matrix(x: f32, y: f32, z: f32) -> Matrix4x4 {
     [
         [1., 0., 0., x],
@@ -122,56 +216,53 @@ pub fn screen_to_pixel_coordinates(screen_width: usize, screen_height: usize) ->
     ]
 }
 
-// TODO not fitting for this file (transformations)
-pub fn triangle_intersects_screen_space(triangle: &Triangle3) -> bool {
-    let mut points_inside_screen_space = 0;
-    for point in triangle.points() {
-        if point.x >= -1. && point.x <= 1. && point.y >= -1. && point.y <= 1. {
-            points_inside_screen_space += 1;
-        }
-    }
+pub fn get_multiplied_points_with_matrix(points: &Vec<Point3>, matrix: &Matrix4x4) -> Vec<Point3> {
+    let mut multiplied_points = Vec::with_capacity(points.len());
+    multiply_points_with_matrix_into(points, matrix, &mut multiplied_points);
+    multiplied_points
+}
 
-    if points_inside_screen_space > 0 {
-        return true;
-    } else {
-        let points = triangle.points();
-        for i in 0..3 {
-            let p1 = points[i];
-            let p2 = points[(i + 1).rem_euclid(3)];
-
-            let k = p1.y - p2.y / (p1.x - p2.x);
-            let m = p1.y - k * p1.x;
-            match k.is_nan() {
-                false => {
-                    // TODOO this does not work correctly, acts like the edges of the triangle are infinite.
-                    // TODO is this inefficient?
-                    if (-1.0..=1.0).contains(&(k * -1.0 + m))
-                        || (-1.0..=1.0).contains(&(k * 1.0 + m))
-                        || (-1.0..=1.0).contains(&((-1.0 - m) / k))
-                        || (-1.0..=1.0).contains(&((1.0 - m) / k))
-                    {
-                        return true;
-                    }
-                }
-                true => {
-                    if (-1.0..=1.0).contains(&k) {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
+/// Like `get_multiplied_points_with_matrix`, but writes the points to `output`,
+/// so that its allocation can be reused between frames.
+pub fn multiply_points_with_matrix_into(
+    points: &[Point3],
+    matrix: &Matrix4x4,
+    output: &mut Vec<Point3>,
+) {
+    output.clear();
+    output.extend(points.iter().map(|point| {
+        let pos_matrix = point.to_matrix4x1();
+        let new_pos_matrix = matrix.multiply(pos_matrix).to_vec3();
+        Point3::from_array(new_pos_matrix)
+    }));
+}
 
-    false
+/// Like `multiply_points_with_matrix_into`, but keeps the homogeneous coordinates instead of dividing by w,
+/// so that triangles can be clipped before the perspective divide.
+pub fn multiply_points_with_matrix_homogeneous_into(
+    points: &[Point3],
+    matrix: &Matrix4x4,
+    output: &mut Vec<[f32; 4]>,
+) {
+    output.clear();
+    output.extend(points.iter().map(|point| {
+        let [[x], [y], [z], [w]] = matrix.multiply(point.to_matrix4x1());
+        [x, y, z, w]
+    }));
 }
 
-pub fn get_multiplied_points_with_matrix(points: &Vec<Point3>, matrix: &Matrix4x4) -> Vec<Point3> {
-    points
+/// Like `get_multiplied_points_with_matrix`, but ignores the translation part of the matrix,
+/// which is what is wanted for directions.
+pub fn get_multiplied_directions_with_matrix(
+    directions: &[Point3],
+    matrix: &Matrix4x4,
+) -> Vec<Point3> {
+    directions
         .iter()
-        .map(|point| {
-            let pos_matrix = point.to_matrix4x1();
-            let new_pos_matrix = matrix.multiply(pos_matrix).to_vec3();
-            Point3::from_array(new_pos_matrix)
+        .map(|direction| {
+            let direction_matrix = [[direction.x], [direction.y], [direction.z], [0.0]];
+            let [[x], [y], [z], _] = matrix.multiply(direction_matrix);
+            Point3 { x, y, z }
         })
         .collect()
 }
@@ -216,6 +307,48 @@ impl Camera {
     }
 }
 
+#[test]
+fn test_rotation_matrix_axis_matches_rotation_matrix_x() {
+    let axis_matrix = rotation_matrix_axis(&Point3::from_array([2.0, 0.0, 0.0]), 0.7);
+    let x_matrix = rotation_matrix_x(0.7);
+    for row_i in 0..4 {
+        for col_i in 0..4 {
+            assert!((axis_matrix[row_i][col_i] - x_matrix[row_i][col_i]).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_inverse() {
+    let matrix = translation_matrix(1.0, -2.0, 3.0)
+        .combine(rotation_matrix_y(0.4))
+        .combine(scale_matrix(2.0, 0.5, 1.0));
+    let product = matrix.combine(matrix.inverse().unwrap());
+    let identity = identity_matrix();
+    for row_i in 0..4 {
+        for col_i in 0..4 {
+            assert!((product[row_i][col_i] - identity[row_i][col_i]).abs() < 1e-5);
+        }
+    }
+    assert!(scale_matrix(1.0, 0.0, 1.0).inverse().is_none());
+    assert!([[0.0; 4]; 4].inverse().is_none());
+
+    // a large translation with a small scale is not mistaken for a singular matrix
+    let far_matrix = translation_matrix(1e6, 0.0, 0.0).combine(scale_matrix(1e-3, 1e-3, 1e-3));
+    let far_inverse = far_matrix.inverse().unwrap();
+    assert!((far_inverse[0][0] - 1e3).abs() < 1e-2);
+    assert!((far_inverse[0][3] / -1e9 - 1.0).abs() < 1e-5);
+
+    // a tiny scale is not mistaken for a singular matrix
+    let tiny_matrix = matrix.map(|row| row.map(|value| value * 1e-7));
+    let product = tiny_matrix.combine(tiny_matrix.inverse().unwrap());
+    for row_i in 0..4 {
+        for col_i in 0..4 {
+            assert!((product[row_i][col_i] - identity[row_i][col_i]).abs() < 1e-5);
+        }
+    }
+}
+
 #[test]
 fn test_distance_to_fit_sphere() {
     let camera = Camera {
@@ -1,52 +1,220 @@
 use super::interface::Buffer;
 use super::interface::Camera;
-use super::pipeline::rasterization::render_triangle;
-use super::pipeline::transformation::get_multiplied_points_with_matrix;
-use super::pipeline::transformation::{screen_to_pixel_coordinates, MatrixTrait};
+use super::pipeline::clipping::{clip_triangle_to_near_plane, ClipVertex};
+use super::pipeline::culling::{GroupCulling, ViewFrustum};
+use super::pipeline::fragment_shader::{Fragment, Lighting, Shader};
+use super::pipeline::rasterization::{render_line, render_triangle};
+use super::pipeline::transformation::{
+    get_multiplied_points_with_matrix, multiply_points_with_matrix_homogeneous_into,
+};
+use super::pipeline::transformation::{screen_to_pixel_coordinates, Matrix4x4, MatrixTrait};
+use crate::general::positions_2d::Point as Point2;
 use crate::general::positions_3d::{dot_product, Mesh, Point as Point3, Triangle as Triangle3};
+use crate::mesh_processing::bvh::Ray;
+use std::str::FromStr;
 
+fn world_to_pixel_matrix(camera: &Camera, width: usize, height: usize) -> Matrix4x4 {
+    let world_to_screen = camera.world_to_screen_space_matrix();
+    let screen_to_pixel = screen_to_pixel_coordinates(width, height);
+    // the matrices are combined is equal to if you would first apply the leftmost matrix to the vector,
+    // then the one to the right of that one, etc.
+    screen_to_pixel.combine(world_to_screen)
+}
+
+/// The ray from the camera through a pixel of an image with the given size,
+/// for example to find the triangle below the mouse cursor.
+/// The direction is a unit vector, so distances along the ray are in world units.
+/// None if the projection of the camera can not be inverted, for example with a very far camera.
+pub fn pixel_ray(
+    camera: &Camera,
+    column: usize,
+    row: usize,
+    width: usize,
+    height: usize,
+) -> Option<Ray> {
+    let pixel_to_world = world_to_pixel_matrix(camera, width, height).inverse()?;
+    // pixels are sampled at their integer coordinates, and the depth goes from 0 at near to 1 at far
+    let [near_point, far_point] =
+        [0.0, 0.5].map(|depth| Point3::from_array([column as f32, row as f32, depth]));
+    let points = get_multiplied_points_with_matrix(&vec![near_point, far_point], &pixel_to_world);
+    Some(Ray {
+        origin: camera.position.clone(),
+        direction: points[1].relative_to(&points[0]).normalized(),
+    })
+}
+
+/// Draws lines given in world space on top of the image, for example the outline of a section
+pub fn render_overlay_lines(
+    lines: &[[Point3; 2]],
+    char_buffer: &mut Buffer<u8>,
+    camera: &Camera,
+    line_char: u8,
+) {
+    let transformation_matrix =
+        world_to_pixel_matrix(camera, char_buffer.width, char_buffer.height);
+    for line in lines {
+        let points = get_multiplied_points_with_matrix(&line.to_vec(), &transformation_matrix);
+        // Skips lines that are partly behind the camera
+        if points.iter().any(|point| point.z <= 0.0) {
+            continue;
+        }
+        render_line(&points[0], &points[1], char_buffer, line_char);
+    }
+}
+
+/// How the normals vary across a triangle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shading {
+    /// Uses the face normal for the whole triangle
+    Flat,
+    /// Runs the shader for the corners with their vertex normals and interpolates the brightness between them
+    Gouraud,
+    /// Interpolates the vertex normals and runs the shader for every pixel, which also shows highlights inside large triangles
+    Phong,
+}
+
+impl FromStr for Shading {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "flat" => Ok(Shading::Flat),
+            "gouraud" => Ok(Shading::Gouraud),
+            "phong" => Ok(Shading::Phong),
+            _ => Err(format!(
+                "'{string}' is not a shading mode, use flat, gouraud or phong"
+            )),
+        }
+    }
+}
+
+/// How the surface of a mesh is drawn
+#[derive(Clone, Copy)]
+pub struct Surface<'a> {
+    pub shader: &'a dyn Shader,
+    pub lighting: &'a Lighting,
+    /// Smooth shading uses the vertex normals of the mesh, triangles without them are shaded flat
+    pub shading: Shading,
+    /// One value for every point of the mesh, passed to the shader as `Fragment::color`.
+    /// Ignored if the amount of values does not match
+    pub vertex_colors: Option<&'a [f32]>,
+}
+
+/// The values of a triangle that are not interpolated
+#[derive(Clone, Copy)]
+struct FaceAttributes {
+    has_uvs: bool,
+    has_colors: bool,
+    material: Option<usize>,
+}
+
+/// `clip_space_points` is only used as scratch space for the transformed vertices,
+/// passing the same buffer every frame avoids reallocating it.
+/// `group_culling` has to be made for `mesh`, it skips groups outside of the view without testing their triangles.
 pub fn render_mesh(
     mesh: &Mesh,
     image_buffer: &mut Buffer<f32>,
     depth_buffer: &mut Buffer<f32>,
+    clip_space_points: &mut Vec<[f32; 4]>,
     camera: &Camera,
-    light_direction: &Point3,
-    ambient_light: f32,
+    surface: &Surface,
+    group_culling: Option<&GroupCulling>,
 ) {
-    let world_to_screen = camera.world_to_screen_space_matrix();
-    let screen_to_pixel = screen_to_pixel_coordinates(image_buffer.width, image_buffer.height);
-    // the matrices are combined is equal to if you would first apply the leftmost matrix to the vector,
-    // then the one to the right of that one, etc.
-    let transformation_matrix = screen_to_pixel.combine(world_to_screen);
+    let transformation_matrix =
+        world_to_pixel_matrix(camera, image_buffer.width, image_buffer.height);
+
+    // the points are not divided by w yet, so that triangles can be clipped at the near plane first
+    multiply_points_with_matrix_homogeneous_into(
+        &mesh.points,
+        &transformation_matrix,
+        clip_space_points,
+    );
+    // the simplified mesh and subdivided meshes have other points
+    let vertex_colors = surface
+        .vertex_colors
+        .filter(|colors| colors.len() == mesh.points.len());
 
-    let pixel_space_points =
-        get_multiplied_points_with_matrix(&mesh.points, &transformation_matrix);
+    let frustum = ViewFrustum::new(image_buffer.width, image_buffer.height);
+    let triangles: Box<dyn Iterator<Item = usize>> = match group_culling {
+        Some(group_culling) => Box::new(group_culling.visible_triangles(
+            &mesh.points,
+            &transformation_matrix,
+            &frustum,
+        )),
+        None => Box::new(0..mesh.triangle_count()),
+    };
 
     // use image::{GrayImage, Luma};
     // let mut triangle_index = 0;
-    for incides_triangle in &mesh.indices_triangles {
-        let triangle = Triangle3::from_indices(incides_triangle, &pixel_space_points).unwrap();
-        // Skips triangles behind the camera
-        // TODO use near instead of 0.0
-        // TODO do this in triangle3d_to_screen_space_triangle function instead
-        if triangle.p1.z <= 0.0 && triangle.p2.z <= 0.0 && triangle.p3.z <= 0.0 {
+    for triangle in triangles {
+        let indices = &mesh.triangles[triangle];
+        let normal = &mesh.face_normals[triangle];
+        // Skips triangles facing away from the camera
+        let triangle_to_camera = camera
+            .position
+            .relative_to(&mesh.points[indices[0] as usize]);
+        if dot_product(normal, &triangle_to_camera) < 0.0 {
             continue;
         }
-        // assumes that both normal and light direction are unit vectors
-        let light_intensity = dot_product(&triangle.normal, &light_direction.inverted()).max(0.0);
-        let surface_brightness =
-            (light_intensity * (1.0 - ambient_light) + ambient_light).clamp(0.0, 1.0);
-
-        if dot_product(&triangle.normal, &camera.position.normalized()) < 0.0 {
+        // Skips triangles outside of the view, so that they do not reach the rasterizer
+        if frustum.excludes(&indices.map(|index| clip_space_points[index as usize])) {
             continue;
         }
 
-        render_triangle(
-            &triangle,
-            image_buffer,
-            depth_buffer,
-            Some(surface_brightness),
-        );
+        let corner_normals = match (surface.shading, mesh.normal_indices(triangle)) {
+            (Shading::Gouraud | Shading::Phong, Some(normal_indices)) => {
+                normal_indices.map(|index| &mesh.normals[index])
+            }
+            _ => [normal; 3],
+        };
+        let corner_uvs = mesh
+            .uv_indices(triangle)
+            .map(|uv_indices| uv_indices.map(|index| &mesh.uvs[index]));
+        let face = FaceAttributes {
+            has_uvs: corner_uvs.is_some(),
+            has_colors: vertex_colors.is_some(),
+            material: mesh.material(triangle),
+        };
+        let mut corners = [0, 1, 2].map(|corner| {
+            let index = indices[corner] as usize;
+            ClipVertex {
+                position: clip_space_points[index],
+                world_position: mesh.points[index].to_array(),
+                normal: corner_normals[corner].to_array(),
+                uv: corner_uvs.map_or([0.0; 2], |uvs| [uvs[corner].x, uvs[corner].y]),
+                color: vertex_colors.map_or(0.0, |colors| colors[index]),
+                brightness: 0.0,
+            }
+        });
+        if surface.shading == Shading::Gouraud {
+            let corner_fragments = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+                .map(|weights| interpolate_fragment(&corners, weights, &face));
+            for (corner, fragment) in corners.iter_mut().zip(corner_fragments) {
+                corner.brightness = surface.shader.shade(&fragment, surface.lighting);
+            }
+        }
+
+        if corners.iter().all(ClipVertex::is_in_front_of_near_plane) {
+            render_clip_space_triangle(
+                &corners,
+                normal,
+                &face,
+                surface,
+                image_buffer,
+                depth_buffer,
+            );
+        } else {
+            for clipped_corners in clip_triangle_to_near_plane(corners) {
+                render_clip_space_triangle(
+                    &clipped_corners,
+                    normal,
+                    &face,
+                    surface,
+                    image_buffer,
+                    depth_buffer,
+                );
+            }
+        }
 
         // --- uncomment to generate debug images ---
 
@@ -69,3 +237,279 @@ pub fn render_mesh(
         // triangle_index += 1;
     }
 }
+
+/// Divides by w and draws the triangle, all corners have to be in front of the near plane
+fn render_clip_space_triangle(
+    corners: &[ClipVertex; 3],
+    normal: &Point3,
+    face: &FaceAttributes,
+    surface: &Surface,
+    image_buffer: &mut Buffer<f32>,
+    depth_buffer: &mut Buffer<f32>,
+) {
+    let [p1, p2, p3] = corners.map(|corner| corner.to_point());
+    let triangle = Triangle3 {
+        p1: &p1,
+        p2: &p2,
+        p3: &p3,
+        normal,
+    };
+    render_triangle(
+        &triangle,
+        image_buffer,
+        depth_buffer,
+        |weights| match surface.shading {
+            Shading::Gouraud => weighted_sum(
+                corners.map(|corner| [corner.brightness]),
+                perspective_correct(corners, weights),
+            )[0],
+            Shading::Flat | Shading::Phong => surface.shader.shade(
+                &interpolate_fragment(corners, weights, face),
+                surface.lighting,
+            ),
+        },
+    );
+}
+
+/// `weights` are the barycentric coordinates of the fragment in the triangle on the screen
+fn interpolate_fragment(
+    corners: &[ClipVertex; 3],
+    screen_weights: [f32; 3],
+    face: &FaceAttributes,
+) -> Fragment {
+    let weights = perspective_correct(corners, screen_weights);
+    Fragment {
+        position: Point3::from_array(weighted_sum(
+            corners.map(|corner| corner.world_position),
+            weights,
+        )),
+        normal: Point3::from_array(weighted_sum(corners.map(|corner| corner.normal), weights))
+            .normalized(),
+        uv: face.has_uvs.then(|| {
+            let [x, y] = weighted_sum(corners.map(|corner| corner.uv), weights);
+            Point2 { x, y }
+        }),
+        color: face
+            .has_colors
+            .then(|| weighted_sum(corners.map(|corner| [corner.color]), weights)[0]),
+        material: face.material,
+        // the depth is divided by w, so it changes linearly across the screen
+        depth: weighted_sum(
+            corners.map(|corner| [corner.position[2] / corner.position[3]]),
+            screen_weights,
+        )[0],
+    }
+}
+
+/// Turns barycentric coordinates on the screen into barycentric coordinates on the triangle in the world.
+/// Parts of a triangle further from the camera are smaller on the screen,
+/// so the values of the far corners have to weigh more than their share of the screen
+fn perspective_correct(corners: &[ClipVertex; 3], screen_weights: [f32; 3]) -> [f32; 3] {
+    let weights: [f32; 3] = std::array::from_fn(|i| screen_weights[i] / corners[i].position[3]);
+    let sum: f32 = weights.iter().sum();
+    if sum == 0.0 {
+        return screen_weights;
+    }
+    weights.map(|weight| weight / sum)
+}
+
+fn weighted_sum<const N: usize>(values: [[f32; N]; 3], weights: [f32; 3]) -> [f32; N] {
+    std::array::from_fn(|i| {
+        values[0][i] * weights[0] + values[1][i] * weights[1] + values[2][i] * weights[2]
+    })
+}
+
+#[test]
+fn test_pixel_ray_projects_back_onto_the_pixel() {
+    let camera = Camera {
+        horizontal_fov: 90.0,
+        vertical_fov: 60.0,
+        position: Point3::from_array([1.0, 2.0, 10.0]),
+        rotation_around_x: 0.3,
+        rotation_around_y: -0.5,
+        near: 1.0,
+        far: 100.0,
+    };
+    let ray = pixel_ray(&camera, 150, 30, 200, 100).unwrap();
+    let points_on_ray = vec![ray.point_at(2.0), ray.point_at(50.0)];
+    let pixels = get_multiplied_points_with_matrix(
+        &points_on_ray,
+        &world_to_pixel_matrix(&camera, 200, 100),
+    );
+    for pixel in pixels {
+        assert!((pixel.x - 150.0).abs() < 1e-3);
+        assert!((pixel.y - 30.0).abs() < 1e-3);
+    }
+}
+
+/// A square that fills the view of `facing_camera()`, with normals tilting to the left and to the right
+#[cfg(test)]
+fn square_with_tilted_normals() -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.points = [[-2.0, -2.0], [2.0, -2.0], [2.0, 2.0], [-2.0, 2.0]]
+        .map(|[x, y]| Point3::from_array([x, y, 0.0]))
+        .to_vec();
+    mesh.normals = vec![
+        Point3::from_array([-0.6, 0.0, 0.8]),
+        Point3::from_array([0.6, 0.0, 0.8]),
+    ];
+    mesh.uvs = vec![Point2 { x: 0.0, y: 0.0 }, Point2 { x: 1.0, y: 0.0 }];
+    let face_normal = Point3::from_array([0.0, 0.0, 1.0]);
+    for (corners, attribute_indices) in [([0, 1, 2], [0, 1, 1]), ([0, 2, 3], [0, 1, 0])] {
+        let triangle = mesh.push_triangle(corners, face_normal.clone());
+        mesh.set_normal_indices(triangle, attribute_indices);
+        mesh.set_uv_indices(triangle, attribute_indices);
+    }
+    mesh
+}
+
+#[cfg(test)]
+fn facing_camera() -> Camera {
+    Camera {
+        horizontal_fov: 90.0,
+        vertical_fov: 90.0,
+        position: Point3::from_array([0.0, 0.0, 2.0]),
+        rotation_around_x: 0.0,
+        rotation_around_y: 0.0,
+        near: 1.0,
+        far: 10.0,
+    }
+}
+
+/// The middle row of a 20 by 20 image
+#[cfg(test)]
+fn render_middle_row(mesh: &Mesh, surface: &Surface) -> Vec<f32> {
+    let mut image_buffer = Buffer::new(20, 20, 0.0);
+    let mut depth_buffer = Buffer::new(20, 20, f32::MAX);
+    render_mesh(
+        mesh,
+        &mut image_buffer,
+        &mut depth_buffer,
+        &mut Vec::new(),
+        &facing_camera(),
+        surface,
+        None,
+    );
+    image_buffer.values[200..220].to_vec()
+}
+
+#[test]
+fn test_smooth_shading_interpolates_vertex_normals() {
+    use super::pipeline::fragment_shader::DiffuseShader;
+    use super::pipeline::light::Light;
+
+    let mesh = square_with_tilted_normals();
+    // shines to the left, so surfaces facing right are bright
+    let lighting = Lighting {
+        lights: vec![Light::directional(Point3::from_array([-1.0, 0.0, 0.0]))],
+        ambient_light: 0.0,
+        camera_position: facing_camera().position,
+        shadow_maps: Vec::new(),
+    };
+    let surface = |shading| Surface {
+        shader: &DiffuseShader,
+        lighting: &lighting,
+        shading,
+        vertex_colors: None,
+    };
+
+    assert!(render_middle_row(&mesh, &surface(Shading::Flat))
+        .iter()
+        .all(|&brightness| brightness == 0.0));
+    for shading in [Shading::Gouraud, Shading::Phong] {
+        let row = render_middle_row(&mesh, &surface(shading));
+        assert!(row.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(row[0] < 0.1 && row[19] > 0.5);
+    }
+}
+
+#[test]
+fn test_shader_gets_interpolated_attributes() {
+    use super::pipeline::light::Light;
+
+    struct UvShader;
+    impl Shader for UvShader {
+        fn shade(&self, fragment: &Fragment, _lighting: &Lighting) -> f32 {
+            assert!(fragment.depth > 0.0 && fragment.depth < 1.0);
+            assert!(fragment.position.z.abs() < 1e-4);
+            fragment.uv.as_ref().unwrap().x * fragment.color.unwrap()
+        }
+    }
+
+    let mesh = square_with_tilted_normals();
+    let lighting = Lighting {
+        lights: vec![Light::directional(Point3::from_array([0.0, 0.0, -1.0]))],
+        ambient_light: 0.0,
+        camera_position: facing_camera().position,
+        shadow_maps: Vec::new(),
+    };
+    let row = render_middle_row(
+        &mesh,
+        &Surface {
+            shader: &UvShader,
+            lighting: &lighting,
+            shading: Shading::Phong,
+            vertex_colors: Some(&[0.5; 4]),
+        },
+    );
+    // the texture coordinates go from 0 on the left to 1 on the right
+    assert!(row[0] < 0.05 && (row[10] - 0.25).abs() < 0.05 && row[19] > 0.45);
+}
+
+#[test]
+fn test_attributes_are_interpolated_with_perspective() {
+    use super::pipeline::light::Light;
+
+    /// Shows where on the floor each pixel is
+    struct DistanceShader;
+    impl Shader for DistanceShader {
+        fn shade(&self, fragment: &Fragment, _lighting: &Lighting) -> f32 {
+            fragment.position.z
+        }
+    }
+
+    // a floor below the camera that reaches far into the distance
+    let mut mesh = Mesh::new();
+    mesh.points = [[-3.0, 1.0], [3.0, 1.0], [3.0, -7.0], [-3.0, -7.0]]
+        .map(|[x, z]| Point3::from_array([x, -1.0, z]))
+        .to_vec();
+    let up = Point3::from_array([0.0, 1.0, 0.0]);
+    mesh.push_triangle([0, 1, 2], up.clone());
+    mesh.push_triangle([0, 2, 3], up);
+    let camera = facing_camera();
+    let lighting = Lighting {
+        lights: vec![Light::headlight()],
+        ambient_light: 0.0,
+        camera_position: camera.position.clone(),
+        shadow_maps: Vec::new(),
+    };
+    let mut image_buffer = Buffer::new(20, 20, f32::MAX);
+    let mut depth_buffer = Buffer::new(20, 20, f32::MAX);
+    render_mesh(
+        &mesh,
+        &mut image_buffer,
+        &mut depth_buffer,
+        &mut Vec::new(),
+        &camera,
+        &Surface {
+            shader: &DistanceShader,
+            lighting: &lighting,
+            shading: Shading::Phong,
+            vertex_colors: None,
+        },
+        None,
+    );
+
+    let mut checked_rows = 0;
+    for row in 0..20 {
+        let Some(z) = image_buffer.get(10, row).filter(|&z| z != f32::MAX) else {
+            continue;
+        };
+        // where the ray through the pixel hits the floor
+        let ray = pixel_ray(&camera, 10, row, 20, 20).unwrap();
+        let distance = (-1.0 - ray.origin.y) / ray.direction.y;
+        assert!((z - ray.point_at(distance).z).abs() < 1e-3);
+        checked_rows += 1;
+    }
+    assert!(checked_rows > 5);
+}
@@ -0,0 +1,93 @@
+use crate::general::positions_3d::{GlobalPoint, Point as Point3};
+use crate::mesh_processing::axis_conversion::Axis;
+use crate::mesh_processing::section::Plane;
+use crossterm::event::{Event, KeyCode};
+
+/// Moves a section plane through the model based on key presses.
+/// Press `s` to show the section along the X axis, and again to switch to the Y and Z axes and to hide it.
+/// Press `[` and `]` to move the plane backwards and forwards along its axis.
+pub struct SectionInputHelper {
+    axis: Option<Axis>,
+    /// Distance of the plane from `center` along the axis
+    offset: f32,
+    step: f32,
+    center: Point3,
+    /// The `Mesh::origin` of the mesh, positions are shown in global coordinates
+    origin: GlobalPoint,
+    has_changed: bool,
+}
+
+impl SectionInputHelper {
+    /// The plane starts at `center` and moves by `step` for each key press
+    pub fn new(center: Point3, step: f32, origin: GlobalPoint) -> Self {
+        SectionInputHelper {
+            axis: None,
+            offset: 0.0,
+            step,
+            center,
+            origin,
+            has_changed: false,
+        }
+    }
+
+    /// Shows the section from the start, with the plane at the global `position` along the axis if given
+    pub fn show(&mut self, axis: Axis, position: Option<f64>) {
+        self.axis = Some(axis);
+        self.offset = match position {
+            Some(position) => {
+                let center = self.origin.add_local(&self.center);
+                (position - center.dot_product(&axis.direction())) as f32
+            }
+            None => 0.0,
+        };
+        self.has_changed = true;
+    }
+
+    pub fn process_input_events(&mut self, events: &[Event]) {
+        for event in events {
+            let Event::Key(key_event) = event else {
+                continue;
+            };
+            match key_event.code {
+                KeyCode::Char('s') => {
+                    self.axis = match self.axis {
+                        None => Some(Axis::PositiveX),
+                        Some(Axis::PositiveX) => Some(Axis::PositiveY),
+                        Some(Axis::PositiveY) => Some(Axis::PositiveZ),
+                        Some(_) => None,
+                    };
+                    self.offset = 0.0;
+                }
+                KeyCode::Char('[') => self.offset -= self.step,
+                KeyCode::Char(']') => self.offset += self.step,
+                _ => continue,
+            }
+            self.has_changed = true;
+        }
+    }
+
+    /// Returns true once after the plane was moved, shown or hidden
+    pub fn take_has_changed(&mut self) -> bool {
+        std::mem::take(&mut self.has_changed)
+    }
+
+    /// `None` while the section is hidden
+    pub fn plane(&self) -> Option<Plane> {
+        let normal = self.axis?.direction();
+        let point = self
+            .center
+            .add(&normal.map(|component| component * self.offset));
+        Some(Plane { point, normal })
+    }
+
+    /// A short description like "section y = 0.25", or `None` while the section is hidden
+    pub fn description(&self) -> Option<String> {
+        let position = self.origin.add_local_rounded(&self.plane()?.point);
+        let (axis_name, position) = match self.axis? {
+            Axis::PositiveX | Axis::NegativeX => ("x", position.x),
+            Axis::PositiveY | Axis::NegativeY => ("y", position.y),
+            Axis::PositiveZ | Axis::NegativeZ => ("z", position.z),
+        };
+        Some(format!("section {axis_name} = {position}"))
+    }
+}
@@ -0,0 +1,137 @@
+use crate::general::positions_3d::Mesh;
+use crate::mesh_processing::subdivision::SubdivisionScheme;
+use crossterm::event::{Event, KeyCode};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// Subdivided meshes with more triangles than this are not created, since they would not fit in memory
+const MAX_TRIANGLE_COUNT: usize = 4_000_000;
+
+/// Switches between subdivision levels of a mesh based on key presses.
+/// Press `+` to subdivide the mesh one more time and `-` to go back to a coarser level.
+/// Levels are kept once they are computed, so that switching back and forth is fast.
+/// New levels are computed on another thread, so that the viewer keeps responding meanwhile.
+pub struct SubdivisionInputHelper {
+    levels: Vec<Mesh>,
+    current_level: usize,
+    scheme: SubdivisionScheme,
+    has_changed: bool,
+    /// The next level while it is computed
+    next_level_receiver: Option<Receiver<Mesh>>,
+    /// `+` was pressed at the last level that fits in `MAX_TRIANGLE_COUNT`
+    is_at_limit: bool,
+    description_has_changed: bool,
+}
+
+impl SubdivisionInputHelper {
+    pub fn new(mesh: Mesh, scheme: SubdivisionScheme) -> Self {
+        SubdivisionInputHelper {
+            levels: vec![mesh],
+            current_level: 0,
+            scheme,
+            has_changed: false,
+            next_level_receiver: None,
+            is_at_limit: false,
+            description_has_changed: false,
+        }
+    }
+
+    /// Also switches to the next level once it has been computed, so it has to be called every frame
+    pub fn process_input_events(&mut self, events: &[Event]) {
+        self.receive_next_level();
+        for event in events {
+            let Event::Key(key_event) = event else {
+                continue;
+            };
+            match key_event.code {
+                // = is on the same key as + on many keyboards
+                KeyCode::Char('+') | KeyCode::Char('=') => self.increase_level(),
+                KeyCode::Char('-') if self.current_level > 0 => {
+                    self.current_level -= 1;
+                    self.is_at_limit = false;
+                    self.has_changed = true;
+                    self.description_has_changed = true;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn increase_level(&mut self) {
+        if self.next_level_receiver.is_some() {
+            return;
+        }
+        if self.current_level + 1 < self.levels.len() {
+            self.current_level += 1;
+            self.has_changed = true;
+            self.description_has_changed = true;
+            return;
+        }
+        let mesh = &self.levels[self.current_level];
+        // every level has about four times as many triangles as the previous one
+        if mesh.triangle_count() * 4 > MAX_TRIANGLE_COUNT {
+            self.is_at_limit = true;
+            self.description_has_changed = true;
+            return;
+        }
+        let mut subdivided_mesh = mesh.clone();
+        let scheme = self.scheme;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            subdivided_mesh.subdivide(scheme);
+            // the viewer might already have been closed, then nobody needs the result
+            let _ = sender.send(subdivided_mesh);
+        });
+        self.next_level_receiver = Some(receiver);
+        self.description_has_changed = true;
+    }
+
+    fn receive_next_level(&mut self) {
+        let Some(receiver) = &self.next_level_receiver else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(mesh) => {
+                self.levels.push(mesh);
+                self.current_level = self.levels.len() - 1;
+                self.has_changed = true;
+            }
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => (),
+        }
+        self.next_level_receiver = None;
+        self.description_has_changed = true;
+    }
+
+    /// Returns true once after the level was changed
+    pub fn take_has_changed(&mut self) -> bool {
+        std::mem::take(&mut self.has_changed)
+    }
+
+    /// Returns true once after `description` changed, also while the mesh stays the same
+    pub fn take_description_has_changed(&mut self) -> bool {
+        std::mem::take(&mut self.description_has_changed)
+    }
+
+    pub fn current_level(&self) -> usize {
+        self.current_level
+    }
+
+    pub fn current_mesh(&self) -> &Mesh {
+        &self.levels[self.current_level]
+    }
+
+    /// A short description like "subdivision level 2", or `None` for the original mesh
+    pub fn description(&self) -> Option<String> {
+        if self.next_level_receiver.is_some() {
+            return Some(format!("subdividing to level {}...", self.levels.len()));
+        }
+        if self.is_at_limit {
+            return Some(format!(
+                "subdivision level {} (more than {MAX_TRIANGLE_COUNT} triangles at the next level)",
+                self.current_level
+            ));
+        }
+        (self.current_level > 0).then(|| format!("subdivision level {}", self.current_level))
+    }
+}